@@ -2,7 +2,7 @@ use std::{
     cell::RefCell,
     io::{stdout, Write},
     rc::Rc,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use crossterm::{
@@ -17,14 +17,25 @@ use termint::{
 };
 
 use crate::{
-    board::board_struct::Board, error::Error, stats::stats_struct::Stats,
-    tui::stats::StatsState,
+    board::board_struct::Board,
+    config::Config,
+    error::Error,
+    stats::{
+        activity::Activity,
+        metrics::MetricsCounter,
+        records::PersonalBest,
+        stats_struct::Stats,
+        storage::{self, Storage},
+    },
+    tui::{overview::OverviewState, stats::StatsState},
 };
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum Screen {
     Game,
     Stats,
+    /// Stats of all the board sizes
+    Overview,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -38,31 +49,74 @@ pub enum State {
 #[derive(Debug)]
 pub struct App {
     pub term: Term,
+    pub config: Config,
     pub board: Board,
     pub time: Duration,
-    pub moves_cnt: usize,
+    pub penalty: Duration,
+    pub metrics: MetricsCounter,
     pub moves: String,
+    pub move_times: Vec<u64>,
+    pub history: Vec<char>,
+    pub redo: Vec<Vec<char>>,
+    pub undos: usize,
+    pub hints: usize,
+    pub seed: Option<u64>,
+    pub next_seed: Option<u64>,
+    pub scramble: String,
     pub screen: Screen,
     pub state: State,
+    pub storage: Box<dyn Storage>,
     pub stats: Stats,
+    /// Size of the board the stats are loaded for, it differs from the game
+    /// board size only while the stats screen shows another size
+    pub stats_size: Vec2,
+    pub activity: Activity,
+    /// Personal bests set by the last solve and when they were set
+    pub new_pbs: Option<(Vec<PersonalBest>, Instant)>,
     pub stat_state: Rc<RefCell<StatsState>>,
     pub stat_board: Board,
+    pub overview: OverviewState,
 }
 
 impl App {
-    /// Creates new [`App`] with board with given size and win length
-    pub fn new(size: Vec2) -> Result<Self, Error> {
+    /// Creates new [`App`] with board with given size and given config
+    pub fn new(size: Vec2, config: Config) -> Result<Self, Error> {
+        let storage = storage::open(config.storage)?;
+        Self::with_storage(size, config, storage)
+    }
+
+    /// Creates new [`App`] with the stats saved in the given storage
+    pub fn with_storage(
+        size: Vec2,
+        config: Config,
+        storage: Box<dyn Storage>,
+    ) -> Result<Self, Error> {
         let mut app = Self {
             term: Term::new().small_screen(App::small_screen()),
+            config,
             board: Board::new(size),
             time: Duration::from_secs(0),
-            moves_cnt: 0,
+            penalty: Duration::from_secs(0),
+            metrics: MetricsCounter::default(),
             moves: String::new(),
+            move_times: Vec::new(),
+            history: Vec::new(),
+            redo: Vec::new(),
+            undos: 0,
+            hints: 0,
+            seed: None,
+            next_seed: None,
+            scramble: String::new(),
             screen: Screen::Game,
             state: State::Idle,
-            stats: Stats::load(&size),
+            stats: storage.load(&size)?,
+            stats_size: size,
+            activity: Activity::load(&*storage),
+            new_pbs: None,
+            storage,
             stat_state: Rc::new(RefCell::new(StatsState::default())),
             stat_board: Board::new(size),
+            overview: OverviewState::default(),
         };
         app.load_stat_board()?;
         Ok(app)
@@ -92,8 +146,14 @@ impl App {
     fn main_loop(&mut self) -> Result<(), Error> {
         self.render()?;
         loop {
-            if poll(Duration::from_millis(100))? {
+            if poll(self.poll_timeout())? {
                 self.event()?;
+            } else {
+                match self.screen {
+                    Screen::Game => self.pb_tick()?,
+                    Screen::Stats => self.replay_tick()?,
+                    Screen::Overview => {}
+                }
             }
         }
     }
@@ -103,6 +163,7 @@ impl App {
         match self.screen {
             Screen::Game => self.render_game(),
             Screen::Stats => self.render_stats(),
+            Screen::Overview => self.render_overview(),
         }
     }
 
@@ -128,6 +189,10 @@ impl App {
                 self.listen_stats(event)?;
                 Ok(false)
             }
+            Screen::Overview => {
+                self.listen_overview(event)?;
+                Ok(false)
+            }
         }
     }
 
@@ -1,23 +1,52 @@
+use chrono::NaiveDate;
 use termint::{
     enums::Color,
     help,
     widgets::{Grad, StrSpanExtension},
 };
 
-use crate::{error::Error, size::Size};
+use crate::{
+    board::board_struct::MAX_SIZE, error::Error, size::Size,
+    stats::export::ExportFormat,
+};
 
 #[derive(Debug, Eq, PartialEq, Clone, Default)]
 pub enum Action {
     #[default]
     Play,
+    Solve(String),
+    Convert(String),
+    Session(SessionAction),
+    Export,
+    Import(String),
     Config,
     Help,
 }
 
+/// Action done with the stats sessions
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum SessionAction {
+    List,
+    New(String),
+    Rename(String, String),
+    Switch(String),
+    Merge(String, String),
+}
+
 /// Parses given arguments and checks for arguments conditions
 #[derive(Debug, Default)]
 pub struct Args {
     pub size: Option<Size>,
+    pub budget: Option<usize>,
+    pub absolute: bool,
+    pub cursor: Option<Size>,
+    pub seed: Option<u64>,
+    pub scramble: Option<String>,
+    pub format: ExportFormat,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub output: Option<String>,
+    pub dry_run: bool,
     pub action: Action,
 }
 
@@ -31,8 +60,43 @@ impl Args {
         while let Some(arg) = args_iter.next() {
             match arg.as_str() {
                 "-s" | "--size" => parsed.parse_size(&mut args_iter)?,
+                "-b" | "--budget" => {
+                    parsed.budget = Some(Args::get_num(&mut args_iter)?)
+                }
+                "-a" | "--absolute" => parsed.absolute = true,
+                "--seed" => {
+                    parsed.seed = Some(Args::get_seed(&mut args_iter)?)
+                }
+                "--scramble" => {
+                    parsed.scramble = Some(Args::get_str(&mut args_iter)?)
+                }
                 "-h" | "--help" => parsed.action = Action::Help,
                 "config" => parsed.action = Action::Config,
+                "solve" => parsed.parse_solve(&mut args_iter)?,
+                "session" => parsed.parse_session(&mut args_iter)?,
+                "stats" => parsed.parse_stats(&mut args_iter)?,
+                "-f" | "--format" => {
+                    let format = Args::get_str(&mut args_iter)?;
+                    parsed.format = format.as_str().try_into()?;
+                }
+                "--from" => {
+                    parsed.from = Some(Args::get_date(&mut args_iter)?)
+                }
+                "--to" => parsed.to = Some(Args::get_date(&mut args_iter)?),
+                "--dry-run" => parsed.dry_run = true,
+                "-o" | "--output" => {
+                    parsed.output = Some(Args::get_str(&mut args_iter)?)
+                }
+                "convert" => {
+                    parsed.action =
+                        Action::Convert(Args::get_str(&mut args_iter)?)
+                }
+                "-c" | "--cursor" => {
+                    parsed.cursor = Some(Size::new(
+                        Args::get_num(&mut args_iter)?,
+                        Args::get_num(&mut args_iter)?,
+                    ))
+                }
                 arg => Err(format!("unexpected argument: '{arg}'"))?,
             }
         }
@@ -51,8 +115,65 @@ impl Args {
             "Usage":
             "loopover" => "Opens 3x3 game\n"
             "loopover" ["options"] => "Behaves according to options\n"
+            "loopover solve" ["state"] ["options"] => {
+                "Prints solution of the board with given state",
+                "State is either scramble string or comma separated",
+                "numbers in row-major order",
+                "Solution is optimal for boards with at most 9 cells\n",
+            }
+            "loopover convert" ["moves"] ["options"] => {
+                "Converts moves between the cursor based notation used in",
+                "the stats and the absolute notation (e.g. 2R 3D')\n",
+            }
+            "loopover session list" => "Lists sessions of the given size\n"
+            "loopover session new" ["name"] => {
+                "Creates new session and makes it active\n",
+            }
+            "loopover session rename" ["old"] ["new"] => "Renames session\n"
+            "loopover session switch" ["name"] => "Makes session active\n"
+            "loopover session merge" ["from"] ["into"] => {
+                "Moves solves of the session to the other session and",
+                "removes it\n",
+            }
+            "loopover stats export" ["options"] => {
+                "Exports solves of all sizes, or only the size given by",
+                "the size option, to the standard output\n",
+            }
+            "loopover stats import" ["file"] ["options"] => {
+                "Imports solves from csTimer, web loopover or JSON Lines",
+                "export to the active session, skipping solves already in",
+                "the stats",
+                "csTimer solves are imported to the size given by the size",
+                "option\n",
+            }
+            "loopover config" => "Opens config file in the editor\n"
             "Options":
             "-s  --size" ["width"] ["height"] => "Sets size of the game\n"
+            "--seed" ["seed"] => "Scrambles the first game with given seed\n"
+            "--scramble" ["state"] => {
+                "Starts the game with given scramble string",
+                "Scramble string is shown in the solve details in stats\n",
+            }
+            "-b  --budget" ["moves"] => {
+                "Sets maximum number of moves of the solution",
+                "Used only with the solve subcommand\n",
+            }
+            "-c  --cursor" ["x"] ["y"] => {
+                "Sets the cursor position used with the convert subcommand",
+                "It's the end position of cursor based moves and the start",
+                "position when converting to the cursor based moves\n",
+            }
+            "-a  --absolute" => {
+                "Prints the solution in the absolute notation (e.g. 2R 3D')",
+                "Used only with the solve subcommand\n",
+            }
+            "-f  --format" ["csv|jsonl"] => {
+                "Sets format of the exported solves (default csv)\n",
+            }
+            "--from" ["YYYY-MM-DD"] => "Exports solves from the date (UTC)\n"
+            "--to" ["YYYY-MM-DD"] => "Exports solves until the date (UTC)\n"
+            "-o  --output" ["file"] => "Exports solves to the file\n"
+            "--dry-run" => "Shows imported solves without saving them\n"
             "-h  --help" => "Prints this help"
         );
     }
@@ -66,10 +187,90 @@ impl Args {
         if size.width < 2 || size.height < 2 {
             return Err(Error::Msg("minimum supported size is 2".into()));
         }
+        if size.width > MAX_SIZE || size.height > MAX_SIZE {
+            return Err(Error::Msg(format!(
+                "maximum supported size is {MAX_SIZE}"
+            )));
+        }
         self.size = Some(size);
         Ok(())
     }
 
+    /// Parses board state to be solved from the given arguments
+    fn parse_solve<T>(&mut self, args: &mut T) -> Result<(), Error>
+    where
+        T: Iterator<Item = String>,
+    {
+        self.action = Action::Solve(Args::get_str(args)?);
+        Ok(())
+    }
+
+    /// Parses session action from the given arguments
+    fn parse_session<T>(&mut self, args: &mut T) -> Result<(), Error>
+    where
+        T: Iterator<Item = String>,
+    {
+        let action = match Args::get_str(args)?.as_str() {
+            "list" => SessionAction::List,
+            "new" => SessionAction::New(Args::get_str(args)?),
+            "rename" => SessionAction::Rename(
+                Args::get_str(args)?,
+                Args::get_str(args)?,
+            ),
+            "switch" => SessionAction::Switch(Args::get_str(args)?),
+            "merge" => SessionAction::Merge(
+                Args::get_str(args)?,
+                Args::get_str(args)?,
+            ),
+            action => Err(format!("unknown session action: '{action}'"))?,
+        };
+        self.action = Action::Session(action);
+        Ok(())
+    }
+
+    /// Parses stats action from the given arguments
+    fn parse_stats<T>(&mut self, args: &mut T) -> Result<(), Error>
+    where
+        T: Iterator<Item = String>,
+    {
+        match Args::get_str(args)?.as_str() {
+            "export" => self.action = Action::Export,
+            "import" => self.action = Action::Import(Args::get_str(args)?),
+            action => Err(format!("unknown stats action: '{action}'"))?,
+        }
+        Ok(())
+    }
+
+    /// Gets date in the YYYY-MM-DD format from args
+    fn get_date<T>(args: &mut T) -> Result<NaiveDate, Error>
+    where
+        T: Iterator<Item = String>,
+    {
+        let val = Args::get_str(args)?;
+        NaiveDate::parse_from_str(&val, "%Y-%m-%d").map_err(|_| {
+            Error::Msg(format!("date in YYYY-MM-DD expected, got '{val}'"))
+        })
+    }
+
+    /// Gets string from args
+    fn get_str<T>(args: &mut T) -> Result<String, Error>
+    where
+        T: Iterator<Item = String>,
+    {
+        args.next()
+            .ok_or(Error::Msg("missing argument parameter".into()))
+    }
+
+    /// Gets seed (u64) from args
+    fn get_seed<T>(args: &mut T) -> Result<u64, Error>
+    where
+        T: Iterator<Item = String>,
+    {
+        let val = Args::get_str(args)?;
+        val.parse::<u64>()
+            .map_err(|_| Error::Msg(format!("number expected, got '{val}'")))
+    }
+
     /// Gets number (usize) from args
     fn get_num<T>(args: &mut T) -> Result<usize, Error>
     where
@@ -1,15 +1,25 @@
-use rand::{seq::SliceRandom, Rng};
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 use termint::{geometry::Vec2, widgets::Widget};
 
 use crate::error::Error;
 
+use super::{notation, rotation::Rotation};
+
+/// Maximum supported width and height of the board, so each cell fits
+/// two characters of the board state
+pub const MAX_SIZE: usize = 36;
+
 /// Represents tictactoe board
 #[derive(Debug, Clone)]
 pub struct Board {
     pub cells: Vec<usize>,
     pub selected: Vec2,
+    /// Number of selected columns and rows, starting at the selected cell
+    pub span: Vec2,
     pub size: Vec2,
     pub small: bool,
+    pub hint: Option<Rotation>,
 }
 
 impl Board {
@@ -18,9 +28,32 @@ impl Board {
         Self {
             cells: (1..=(size.x * size.y)).collect(),
             selected: Vec2::new(0, 0),
+            span: Vec2::new(1, 1),
             size,
             small: size.x * size.y > 9,
+            hint: None,
+        }
+    }
+
+    /// Creates new [`Board`] with given cells
+    pub fn from_cells(size: Vec2, cells: Vec<usize>) -> Result<Self, Error> {
+        let mut sorted = cells.clone();
+        sorted.sort_unstable();
+        if !sorted.iter().copied().eq(1..=(size.x * size.y)) {
+            return Err(Error::Msg(format!(
+                "board state must contain each number from 1 to {}",
+                size.x * size.y
+            )));
         }
+
+        let mut board = Self::new(size);
+        board.cells = cells;
+        if !board.reachable() {
+            return Err(Error::Msg(
+                "board state can't be reached by rotations".into(),
+            ));
+        }
+        Ok(board)
     }
 
     /// Checks if the [`Board`] is solved
@@ -33,11 +66,33 @@ impl Board {
         true
     }
 
-    /// Scrambles the [`Board`]
-    pub fn scramble(&mut self) {
-        self.shuffle();
-        while self.solved() {
-            self.cells.shuffle(&mut rand::thread_rng());
+    /// Checks whether the cells of the [`Board`] are odd permutation
+    pub fn odd(&self) -> bool {
+        odd_permutation(&self.cells)
+    }
+
+    /// Checks whether the [`Board`] state can be reached by rotations. When
+    /// both sides are odd, each rotation is even permutation, so only the
+    /// even permutations can be reached.
+    pub fn reachable(&self) -> bool {
+        self.size.x.is_multiple_of(2)
+            || self.size.y.is_multiple_of(2)
+            || !self.odd()
+    }
+
+    /// Scrambles the [`Board`] to uniformly random reachable state, the same
+    /// seed always gives the same scramble on any platform and version
+    pub fn scramble(&mut self, seed: u64) {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        loop {
+            self.restart();
+            shuffle(&mut self.cells, &mut rng);
+            if !self.reachable() {
+                self.cells.swap(0, 1);
+            }
+            if !self.solved() {
+                break;
+            }
         }
     }
 
@@ -57,9 +112,11 @@ impl Board {
             self.selected.y.checked_sub(1).unwrap_or(self.size.y - 1);
     }
 
-    /// Rotates selected column up
+    /// Rotates selected columns up
     pub fn move_up(&mut self) {
-        self.rotate(self.selected.x, self.size.y, self.size.x as isize);
+        for x in self.span_columns() {
+            self.rotate(Rotation::Up(x));
+        }
     }
 
     /// Moves selected up
@@ -67,10 +124,11 @@ impl Board {
         self.selected.y = (self.selected.y + 1) % self.size.y;
     }
 
-    /// Rotates selected column down
+    /// Rotates selected columns down
     pub fn move_down(&mut self) {
-        let start = self.selected.x + self.size.x * (self.size.y - 1);
-        self.rotate(start, self.size.y, -(self.size.x as isize));
+        for x in self.span_columns() {
+            self.rotate(Rotation::Down(x));
+        }
     }
 
     /// Moves selected up
@@ -79,10 +137,11 @@ impl Board {
             self.selected.x.checked_sub(1).unwrap_or(self.size.x - 1);
     }
 
-    /// Rotates selected row left
+    /// Rotates selected rows left
     pub fn move_left(&mut self) {
-        let start = self.selected.y * self.size.x;
-        self.rotate(start, self.size.x, 1);
+        for y in self.span_rows() {
+            self.rotate(Rotation::Left(y));
+        }
     }
 
     /// Moves selected up
@@ -90,26 +149,113 @@ impl Board {
         self.selected.x = (self.selected.x + 1) % self.size.x;
     }
 
-    /// Rotates selected row right
+    /// Rotates selected rows right
     pub fn move_right(&mut self) {
-        let start = self.selected.y * self.size.x + self.size.x - 1;
-        self.rotate(start, self.size.x, -1);
+        for y in self.span_rows() {
+            self.rotate(Rotation::Right(y));
+        }
+    }
+
+    /// Adds column to the selection, returns whether selection changed
+    pub fn grow_columns(&mut self) -> bool {
+        let old = self.span.x;
+        self.span.x = (self.span.x + 1).min(self.size.x);
+        old != self.span.x
     }
 
-    /// Applies reverse solution to the board to reconstruct the scramble
+    /// Removes column from the selection, returns whether selection changed
+    pub fn shrink_columns(&mut self) -> bool {
+        let old = self.span.x;
+        self.span.x = self.span.x.saturating_sub(1).max(1);
+        old != self.span.x
+    }
+
+    /// Adds row to the selection, returns whether selection changed
+    pub fn grow_rows(&mut self) -> bool {
+        let old = self.span.y;
+        self.span.y = (self.span.y + 1).min(self.size.y);
+        old != self.span.y
+    }
+
+    /// Removes row from the selection, returns whether selection changed
+    pub fn shrink_rows(&mut self) -> bool {
+        let old = self.span.y;
+        self.span.y = self.span.y.saturating_sub(1).max(1);
+        old != self.span.y
+    }
+
+    /// Gets indices of the selected columns
+    pub fn span_columns(&self) -> Vec<usize> {
+        (0..self.span.x)
+            .map(|i| (self.selected.x + i) % self.size.x)
+            .collect()
+    }
+
+    /// Gets indices of the selected rows
+    pub fn span_rows(&self) -> Vec<usize> {
+        (0..self.span.y)
+            .map(|i| (self.selected.y + i) % self.size.y)
+            .collect()
+    }
+
+    /// Checks whether cell on given position is selected
+    pub fn is_selected(&self, x: usize, y: usize) -> bool {
+        let dx = (x + self.size.x - self.selected.x) % self.size.x;
+        let dy = (y + self.size.y - self.selected.y) % self.size.y;
+        dx < self.span.x && dy < self.span.y
+    }
+
+    /// Applies given rotation to the board
+    pub fn rotate(&mut self, rot: Rotation) {
+        rot.apply(&mut self.cells, self.size);
+    }
+
+    /// Applies reverse solution to the board to reconstruct the scramble.
+    /// Solution can be either in the cursor based or the absolute notation.
     pub fn apply_solution(
         &mut self,
         moves: &str,
         end: Vec2,
     ) -> Result<(), Error> {
         self.restart();
+        if !notation::is_absolute(moves) {
+            return self.revert_moves(moves, end);
+        }
+
         self.select(end);
+        self.span = Vec2::new(1, 1);
+        let rots = notation::parse_absolute(moves, self.size)?;
+        for rot in rots.iter().rev() {
+            self.rotate(rot.inverse());
+        }
+        Ok(())
+    }
+
+    /// Reverts given cursor based moves, which end with the cursor on the
+    /// given position. Recorded moves always start with single cell
+    /// selected, so the selection is replayed first and each move is
+    /// reverted with the selection it was made with.
+    pub fn revert_moves(
+        &mut self,
+        moves: &str,
+        end: Vec2,
+    ) -> Result<(), Error> {
+        self.select(end);
+        self.span = Vec2::new(1, 1);
+
+        let mut spans = Vec::new();
+        for c in moves.chars().filter(|c| !c.is_whitespace()) {
+            spans.push((c, self.span));
+            if matches!(c, '>' | '<' | 'v' | '^') {
+                self.apply_move(c)?;
+            }
+        }
 
-        for c in moves.chars().rev() {
-            if c.is_whitespace() {
-                continue;
+        for (c, span) in spans.into_iter().rev() {
+            self.span = span;
+            if !matches!(c, '>' | '<' | 'v' | '^') {
+                self.apply_rev_move(c)?;
             }
-            self.apply_rev_move(c)?;
         }
         Ok(())
     }
@@ -137,6 +283,10 @@ impl Board {
                 self.down();
             }
             'd' => self.down(),
+            '>' => _ = self.grow_columns(),
+            '<' => _ = self.shrink_columns(),
+            'v' => _ = self.grow_rows(),
+            '^' => _ = self.shrink_rows(),
             _ => {
                 return Err(Error::Msg(
                     "Invalid character in solution".to_string(),
@@ -146,6 +296,25 @@ impl Board {
         Ok(())
     }
 
+    /// Gets the move reverting given move
+    pub fn inverse_move(m: char) -> char {
+        match m {
+            'U' => 'D',
+            'D' => 'U',
+            'L' => 'R',
+            'R' => 'L',
+            'u' => 'd',
+            'd' => 'u',
+            'l' => 'r',
+            'r' => 'l',
+            '>' => '<',
+            '<' => '>',
+            'v' => '^',
+            '^' => 'v',
+            m => m,
+        }
+    }
+
     /// Removes given move
     pub fn apply_rev_move(&mut self, m: char) -> Result<(), Error> {
         match m {
@@ -169,6 +338,10 @@ impl Board {
                 self.up();
             }
             'd' => self.up(),
+            '>' => _ = self.shrink_columns(),
+            '<' => _ = self.grow_columns(),
+            'v' => _ = self.shrink_rows(),
+            '^' => _ = self.grow_rows(),
             _ => {
                 return Err(Error::Msg(
                     "Invalid character in solution".to_string(),
@@ -179,43 +352,22 @@ impl Board {
     }
 }
 
-impl Board {
-    /// Shuffles the [`Board`]
-    fn shuffle(&mut self) {
-        let mut rng = rand::thread_rng();
-        let lim = self.size.x * self.size.x * self.size.y * self.size.y;
-        for _ in 0..lim {
-            let sel = Vec2::new(
-                rng.gen_range(0..self.size.x),
-                rng.gen_range(0..self.size.y),
-            );
-
-            match rng.gen_range(0..4) {
-                0 => self.rotate(sel.x, self.size.y, self.size.x as isize),
-                1 => {
-                    let start = sel.x + self.size.x * (self.size.y - 1);
-                    self.rotate(start, self.size.y, -(self.size.x as isize));
-                }
-                2 => self.rotate(sel.y * self.size.x, self.size.x, 1),
-                3 => {
-                    let start = sel.y * self.size.x + self.size.x - 1;
-                    self.rotate(start, self.size.x, -1);
-                }
-                _ => {}
-            }
-        }
-    }
-
-    /// Applies rotation from the start position with given step
-    fn rotate(&mut self, mut start: usize, size: usize, step: isize) {
-        let cell = self.cells[start];
-        for _ in 1..size {
-            let next = (start as isize + step) as usize;
-            self.cells[start] = self.cells[next];
-            start = next;
-        }
-        self.cells[start] = cell;
+/// Checks whether given cells are odd permutation
+pub fn odd_permutation(cells: &[usize]) -> bool {
+    let mut visited = vec![false; cells.len()];
+    let mut cycles = 0;
+    for i in 0..cells.len() {
+        if visited[i] {
+            continue;
+        }
+        cycles += 1;
+        let mut j = i;
+        while !visited[j] {
+            visited[j] = true;
+            j = cells[j] - 1;
+        }
     }
+    (cells.len() - cycles) % 2 == 1
 }
 
 impl From<Board> for Box<dyn Widget> {
@@ -223,3 +375,82 @@ impl From<Board> for Box<dyn Widget> {
         Box::new(value)
     }
 }
+
+/// Shuffles given cells using the Fisher-Yates shuffle. It's used instead
+/// of the rand shuffle, which isn't guaranteed to stay the same across the
+/// rand versions.
+fn shuffle(cells: &mut [usize], rng: &mut ChaCha8Rng) {
+    for i in (1..cells.len()).rev() {
+        let bound = i as u64 + 1;
+        // Values in the incomplete last range are skipped to avoid bias
+        let zone = u64::MAX - u64::MAX % bound;
+        let j = loop {
+            let val = rng.next_u64();
+            if val < zone {
+                break val % bound;
+            }
+        };
+        cells.swap(i, j as usize);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Applies given moves to the solved board, reconstructs the scramble
+    /// from them and replays them again
+    fn round_trip(moves: &str, size: Vec2) -> Board {
+        let apply = |board: &mut Board| {
+            for c in moves.chars().filter(|c| !c.is_whitespace()) {
+                board.apply_move(c).unwrap();
+            }
+        };
+        let mut board = Board::new(size);
+        apply(&mut board);
+        let end = board.selected;
+
+        let mut replayed = Board::new(size);
+        replayed.apply_solution(moves, end).unwrap();
+        assert_eq!(replayed.span, Vec2::new(1, 1));
+        apply(&mut replayed);
+        assert_eq!(replayed.selected, end);
+        assert_eq!(replayed.span, board.span);
+        replayed
+    }
+
+    /// Scrambles have to stay the same, so the seeds can be shared
+    #[test]
+    fn scramble_is_pinned() {
+        let mut board = Board::new(Vec2::new(3, 3));
+        board.scramble(42);
+        assert_eq!(board.cells, [8, 2, 9, 3, 6, 5, 7, 1, 4]);
+
+        let mut board = Board::new(Vec2::new(4, 4));
+        board.scramble(1);
+        assert_eq!(
+            board.cells,
+            [14, 3, 7, 5, 8, 10, 9, 4, 13, 12, 11, 16, 1, 15, 6, 2]
+        );
+    }
+
+    #[test]
+    fn shrinking_single_cell() {
+        let mut board = round_trip("< ^ R D", Vec2::new(3, 3));
+        assert!(board.solved());
+        assert_eq!(board.span, Vec2::new(1, 1));
+    }
+
+    #[test]
+    fn growing_past_board() {
+        let mut board = round_trip("> > > > R v v v D <", Vec2::new(3, 3));
+        assert!(board.solved());
+        assert_eq!(board.span, Vec2::new(2, 3));
+    }
+
+    #[test]
+    fn block_solution_is_reverted() {
+        let mut board = round_trip("> R d v D r < U", Vec2::new(4, 3));
+        assert!(board.solved());
+    }
+}
@@ -8,6 +8,7 @@ use termint::{
 use super::{
     asci::{get_cell, get_min_cell, get_min_sel_cell, get_sel_cell},
     board_struct::Board,
+    rotation::Rotation,
 };
 
 impl Widget for Board {
@@ -54,7 +55,7 @@ impl Board {
         for y in 0..self.size.y {
             for x in 0..self.size.x {
                 let val = self.cells[x + y * self.size.x];
-                let cell = if self.selected.x == x && self.selected.y == y {
+                let cell = if self.is_selected(x, y) {
                     get_sel_cell(val)
                 } else {
                     get_cell(val)
@@ -69,6 +70,39 @@ impl Board {
         }
 
         grid.render(buffer);
+        self.render_hint(buffer, (x, y));
+    }
+
+    /// Renders arrows showing the hinted rotation over the rotated line
+    fn render_hint(&self, buffer: &mut Buffer, (w, h): (usize, usize)) {
+        let Some(hint) = self.hint else {
+            return;
+        };
+
+        let (cells, arrow) = match hint {
+            Rotation::Up(x) => (self.column(x), '↑'),
+            Rotation::Down(x) => (self.column(x), '↓'),
+            Rotation::Left(y) => (self.row(y), '←'),
+            Rotation::Right(y) => (self.row(y), '→'),
+        };
+        // Board bigger than the buffer is clipped, so the arrows are too
+        for (x, y) in cells {
+            let (x, y) = (x * w + w / 2, y * h);
+            if x < buffer.width() && y < buffer.height() {
+                let pos = Vec2::new(buffer.x() + x, buffer.y() + y);
+                buffer.set_val(arrow, &pos);
+            }
+        }
+    }
+
+    /// Gets coordinates of all cells in the column
+    fn column(&self, x: usize) -> Vec<(usize, usize)> {
+        (0..self.size.y).map(|y| (x, y)).collect()
+    }
+
+    /// Gets coordinates of all cells in the row
+    fn row(&self, y: usize) -> Vec<(usize, usize)> {
+        (0..self.size.x).map(|x| (x, y)).collect()
     }
 
     fn cell_color(&self, mut cell: usize) -> Color {
@@ -78,3 +112,20 @@ impl Board {
         Color::Rgb((200 - x) as u8, y as u8, x as u8)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Hint arrows outside of the buffer smaller than the board are skipped
+    #[test]
+    fn hint_is_clipped() {
+        let mut board = Board::new(Vec2::new(5, 5));
+        let mut buffer = Buffer::empty((0, 0, 20, 8));
+        for hint in [Rotation::Right(4), Rotation::Down(1)] {
+            board.hint = Some(hint);
+            board.render_hint(&mut buffer, (11, 5));
+        }
+        assert_eq!(buffer[Vec2::new(16, 5)].val, '↓');
+    }
+}
@@ -1,3 +1,6 @@
 mod asci;
 pub mod board_struct;
 mod board_tui;
+pub mod notation;
+pub mod rotation;
+mod state;
@@ -0,0 +1,212 @@
+//! Conversion between the cursor based notation, used for recording the
+//! solves, and the absolute notation used by the web loopover.
+//!
+//! Absolute notation addresses rows and columns by their one-based index.
+//! `2R` rotates second row right and `3D` rotates third column down. Moves
+//! followed by `'` rotate to the opposite direction, so `3D'` rotates third
+//! column up. `L` and `U` are accepted as well.
+//!
+//! Cursor based notation uses uppercase letters for rotating selected rows
+//! or columns and lowercase letters for moving the cursor. Selection always
+//! starts with single cell and is resized by `>`/`<` (adding/removing
+//! column) and `v`/`^` (adding/removing row).
+
+use termint::geometry::Vec2;
+
+use crate::error::Error;
+
+use super::{board_struct::Board, rotation::Rotation};
+
+/// Checks whether given moves are in the absolute notation
+pub fn is_absolute(moves: &str) -> bool {
+    moves.chars().any(|c| c.is_ascii_digit())
+}
+
+/// Converts cursor based moves, which end with the cursor on the given
+/// position, to the rotations
+pub fn to_rotations(
+    moves: &str,
+    end: Vec2,
+    size: Vec2,
+) -> Result<Vec<Rotation>, Error> {
+    let mut board = Board::new(size);
+    board.revert_moves(moves, end)?;
+
+    // Block moves are converted to rotations of each of the lines
+    let mut rots = Vec::new();
+    for c in moves.chars().filter(|c| !c.is_whitespace()) {
+        let cols = board.span_columns();
+        let rows = board.span_rows();
+        match c {
+            'U' => rots.extend(cols.into_iter().map(Rotation::Up)),
+            'D' => rots.extend(cols.into_iter().map(Rotation::Down)),
+            'L' => rots.extend(rows.into_iter().map(Rotation::Left)),
+            'R' => rots.extend(rows.into_iter().map(Rotation::Right)),
+            _ => {}
+        }
+        board.apply_move(c)?;
+    }
+    Ok(rots)
+}
+
+/// Converts cursor based moves, which end with the cursor on the given
+/// position, to the absolute notation
+pub fn to_absolute(
+    moves: &str,
+    end: Vec2,
+    size: Vec2,
+) -> Result<String, Error> {
+    Ok(format_absolute(&to_rotations(moves, end, size)?))
+}
+
+/// Converts moves in the absolute notation to the cursor based moves,
+/// starting with the cursor on the given position
+pub fn to_cursor(
+    moves: &str,
+    start: Vec2,
+    size: Vec2,
+) -> Result<String, Error> {
+    let rots = parse_absolute(moves, size)?;
+    Ok(Rotation::to_moves(&rots, start, size))
+}
+
+/// Formats given rotations to the absolute notation
+pub fn format_absolute(rots: &[Rotation]) -> String {
+    let moves: Vec<String> = rots
+        .iter()
+        .map(|rot| match rot {
+            Rotation::Up(x) => format!("{}D'", x + 1),
+            Rotation::Down(x) => format!("{}D", x + 1),
+            Rotation::Left(y) => format!("{}R'", y + 1),
+            Rotation::Right(y) => format!("{}R", y + 1),
+        })
+        .collect();
+    moves.join(" ")
+}
+
+/// Parses moves in the absolute notation to the rotations
+pub fn parse_absolute(
+    moves: &str,
+    size: Vec2,
+) -> Result<Vec<Rotation>, Error> {
+    moves
+        .split_whitespace()
+        .map(|m| parse_move(m, size))
+        .collect()
+}
+
+/// Parses single move in the absolute notation
+fn parse_move(m: &str, size: Vec2) -> Result<Rotation, Error> {
+    let err = || Error::Msg(format!("invalid move '{m}'"));
+
+    let (m, prime) = match m.strip_suffix('\'') {
+        Some(m) => (m, true),
+        None => (m, false),
+    };
+    let dir_pos = m.find(|c: char| !c.is_ascii_digit()).ok_or_else(err)?;
+    let (id, dir) = m.split_at(dir_pos);
+    let id = id.parse::<usize>().map_err(|_| err())?;
+    if id == 0 {
+        return Err(err());
+    }
+
+    let id = id - 1;
+    let (rot, len) = match dir {
+        "U" => (Rotation::Up(id), size.x),
+        "D" => (Rotation::Down(id), size.x),
+        "L" => (Rotation::Left(id), size.y),
+        "R" => (Rotation::Right(id), size.y),
+        _ => return Err(err()),
+    };
+    if id >= len {
+        return Err(err());
+    }
+
+    match prime {
+        true => Ok(rot.inverse()),
+        false => Ok(rot),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: Vec2 = Vec2 { x: 3, y: 3 };
+
+    /// Gets the cursor position after applying given moves from given start
+    fn end(moves: &str, start: Vec2, size: Vec2) -> Vec2 {
+        let mut board = Board::new(size);
+        board.selected = start;
+        for c in moves.chars().filter(|c| !c.is_whitespace()) {
+            board.apply_move(c).unwrap();
+        }
+        board.selected
+    }
+
+    #[test]
+    fn parses_absolute() {
+        let rots = parse_absolute("2R 3D' 1U 3L'", SIZE).unwrap();
+        assert_eq!(
+            rots,
+            [
+                Rotation::Right(1),
+                Rotation::Up(2),
+                Rotation::Up(0),
+                Rotation::Right(2),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_absolute() {
+        for moves in ["4R", "0D", "R", "2X", "2R''"] {
+            assert!(parse_absolute(moves, SIZE).is_err(), "{moves}");
+        }
+    }
+
+    #[test]
+    fn formats_absolute() {
+        let moves = "1R 2D' 3R' 2D";
+        let rots = parse_absolute(moves, SIZE).unwrap();
+        assert_eq!(format_absolute(&rots), moves);
+    }
+
+    #[test]
+    fn cursor_rotations() {
+        let moves = "R d D";
+        let end = end(moves, Vec2::new(0, 0), SIZE);
+        let rots = to_rotations(moves, end, SIZE).unwrap();
+        assert_eq!(rots, [Rotation::Right(0), Rotation::Down(1)]);
+    }
+
+    #[test]
+    fn block_rotations() {
+        let rots = to_rotations("> v R", Vec2::new(0, 0), SIZE).unwrap();
+        assert_eq!(rots, [Rotation::Right(0), Rotation::Right(1)]);
+    }
+
+    #[test]
+    fn absolute_round_trip() {
+        let size = Vec2::new(4, 3);
+        let moves = "1R 2D' 3R' 4D 2R 1D'";
+        for start in [Vec2::new(0, 0), Vec2::new(3, 2), Vec2::new(1, 2)] {
+            let cursor = to_cursor(moves, start, size).unwrap();
+            let end = end(&cursor, start, size);
+            assert_eq!(to_absolute(&cursor, end, size).unwrap(), moves);
+        }
+    }
+
+    #[test]
+    fn cursor_round_trip() {
+        let start = Vec2::new(0, 0);
+        let moves = "R d D r U";
+        let end = end(moves, start, SIZE);
+        let rots = to_rotations(moves, end, SIZE).unwrap();
+
+        let absolute = to_absolute(moves, end, SIZE).unwrap();
+        let cursor = to_cursor(&absolute, start, SIZE).unwrap();
+        let cursor_end = self::end(&cursor, start, SIZE);
+        assert_eq!(to_rotations(&cursor, cursor_end, SIZE).unwrap(), rots);
+    }
+}
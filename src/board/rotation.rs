@@ -0,0 +1,125 @@
+use termint::geometry::Vec2;
+
+/// Rotation of a single row or column of the board by one cell
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rotation {
+    /// Rotates column with given index up
+    Up(usize),
+    /// Rotates column with given index down
+    Down(usize),
+    /// Rotates row with given index left
+    Left(usize),
+    /// Rotates row with given index right
+    Right(usize),
+}
+
+impl Rotation {
+    /// Gets all the rotations possible on the board with given size
+    pub fn all(size: Vec2) -> Vec<Self> {
+        let mut rots = Vec::with_capacity(2 * (size.x + size.y));
+        for x in 0..size.x {
+            rots.push(Rotation::Up(x));
+            rots.push(Rotation::Down(x));
+        }
+        for y in 0..size.y {
+            rots.push(Rotation::Left(y));
+            rots.push(Rotation::Right(y));
+        }
+        rots
+    }
+
+    /// Gets the rotation undoing this rotation
+    pub fn inverse(&self) -> Self {
+        match *self {
+            Rotation::Up(x) => Rotation::Down(x),
+            Rotation::Down(x) => Rotation::Up(x),
+            Rotation::Left(y) => Rotation::Right(y),
+            Rotation::Right(y) => Rotation::Left(y),
+        }
+    }
+
+    /// Applies the rotation to the given cells of board with given size
+    pub fn apply(&self, cells: &mut [usize], size: Vec2) {
+        match *self {
+            Rotation::Up(x) => rotate(cells, x, size.y, size.x as isize),
+            Rotation::Down(x) => {
+                let start = x + size.x * (size.y - 1);
+                rotate(cells, start, size.y, -(size.x as isize));
+            }
+            Rotation::Left(y) => rotate(cells, y * size.x, size.x, 1),
+            Rotation::Right(y) => {
+                let start = y * size.x + size.x - 1;
+                rotate(cells, start, size.x, -1);
+            }
+        }
+    }
+
+    /// Converts given rotations to the cursor based moves notation, starting
+    /// with the cursor on the given position
+    pub fn to_moves(rots: &[Rotation], start: Vec2, size: Vec2) -> String {
+        let mut cur = start;
+        let mut moves = Vec::new();
+        for rot in rots {
+            match *rot {
+                Rotation::Up(x) | Rotation::Down(x) => {
+                    walk(&mut moves, cur.x, x, size.x, ('r', 'l'));
+                    cur.x = x;
+                }
+                Rotation::Left(y) | Rotation::Right(y) => {
+                    walk(&mut moves, cur.y, y, size.y, ('d', 'u'));
+                    cur.y = y;
+                }
+            }
+
+            match rot {
+                Rotation::Up(_) => {
+                    moves.push('U');
+                    cur.y = cur.y.checked_sub(1).unwrap_or(size.y - 1);
+                }
+                Rotation::Down(_) => {
+                    moves.push('D');
+                    cur.y = (cur.y + 1) % size.y;
+                }
+                Rotation::Left(_) => {
+                    moves.push('L');
+                    cur.x = cur.x.checked_sub(1).unwrap_or(size.x - 1);
+                }
+                Rotation::Right(_) => {
+                    moves.push('R');
+                    cur.x = (cur.x + 1) % size.x;
+                }
+            }
+        }
+
+        let moves: Vec<String> = moves.iter().map(|c| c.to_string()).collect();
+        moves.join(" ")
+    }
+}
+
+/// Applies rotation from the start position with given step
+fn rotate(cells: &mut [usize], mut start: usize, size: usize, step: isize) {
+    let cell = cells[start];
+    for _ in 1..size {
+        let next = (start as isize + step) as usize;
+        cells[start] = cells[next];
+        start = next;
+    }
+    cells[start] = cell;
+}
+
+/// Adds the shortest cursor moves from `from` to `to` on the line with given
+/// length, using given (forward, backward) move characters
+fn walk(
+    moves: &mut Vec<char>,
+    from: usize,
+    to: usize,
+    len: usize,
+    (fwd, back): (char, char),
+) {
+    let steps = (to + len - from) % len;
+    if steps <= len - steps {
+        moves.extend(std::iter::repeat_n(fwd, steps));
+    } else {
+        moves.extend(std::iter::repeat_n(back, len - steps));
+    }
+}
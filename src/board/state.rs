@@ -0,0 +1,117 @@
+use termint::geometry::Vec2;
+
+use crate::error::Error;
+
+use super::board_struct::Board;
+
+/// Base in which the cells are encoded in the board state
+const BASE: u32 = 36;
+/// Maximum number of cells, which fit two characters each in the state
+const MAX_CELLS: usize = (BASE * BASE) as usize;
+
+impl Board {
+    /// Gets compact textual state of the board in format `<w>x<h>:<cells>`,
+    /// where each cell is zero-based number in base 36. When the board has
+    /// more than 36 cells, each cell takes two characters.
+    pub fn state(&self) -> String {
+        let width = Self::cell_width(self.size);
+        let cells: String = self
+            .cells
+            .iter()
+            .map(|c| {
+                let mut val = c - 1;
+                let mut enc = vec!['0'; width];
+                for ch in enc.iter_mut().rev() {
+                    let digit = (val % BASE as usize) as u32;
+                    *ch = char::from_digit(digit, BASE).unwrap_or('0');
+                    val /= BASE as usize;
+                }
+                enc.into_iter().collect::<String>()
+            })
+            .collect();
+        format!("{}x{}:{cells}", self.size.x, self.size.y)
+    }
+
+    /// Creates new [`Board`] from the state created by [`Board::state`]
+    pub fn from_state(state: &str) -> Result<Self, Error> {
+        let err = || Error::Msg(format!("invalid board state '{state}'"));
+
+        let (size, cells) = state.trim().split_once(':').ok_or_else(err)?;
+        let (w, h) = size.split_once('x').ok_or_else(err)?;
+        let size: Vec2 = Vec2::new(
+            w.parse().map_err(|_| err())?,
+            h.parse().map_err(|_| err())?,
+        );
+        if size.x < 2 || size.y < 2 {
+            return Err(Error::Msg("minimum supported size is 2".into()));
+        }
+        match size.x.checked_mul(size.y) {
+            Some(cnt) if cnt <= MAX_CELLS => {}
+            _ => {
+                return Err(Error::Msg(format!(
+                    "board state supports at most {MAX_CELLS} cells"
+                )))
+            }
+        }
+
+        let chars: Vec<char> = cells.chars().collect();
+        let cells = chars
+            .chunks(Self::cell_width(size))
+            .map(|c| {
+                c.iter().try_fold(0, |acc, ch| {
+                    let digit = ch.to_digit(BASE).ok_or_else(err)?;
+                    Ok(acc * BASE as usize + digit as usize)
+                })
+            })
+            .map(|c: Result<usize, Error>| c.map(|c| c + 1))
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::from_cells(size, cells)
+    }
+
+    /// Parses the board state, which is either in the format of
+    /// [`Board::state`] or comma separated cells of board with given size
+    pub fn parse(state: &str, size: Vec2) -> Result<Self, Error> {
+        if state.contains(':') {
+            return Self::from_state(state);
+        }
+
+        let cells = state
+            .split(',')
+            .map(|c| {
+                c.trim().parse::<usize>().map_err(|_| {
+                    Error::Msg(format!("number expected, got '{c}'"))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::from_cells(size, cells)
+    }
+
+    /// Gets number of characters each cell takes in the state
+    fn cell_width(size: Vec2) -> usize {
+        match size.x * size.y {
+            n if n <= BASE as usize => 1,
+            _ => 2,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn largest_state_roundtrip() {
+        let board = Board::new(Vec2::new(36, 36));
+        let state = board.state();
+        assert!(state.ends_with("zz"));
+        assert_eq!(Board::from_state(&state).unwrap().cells, board.cells);
+    }
+
+    #[test]
+    fn unencodable_size_is_rejected() {
+        let huge = format!("{}x2:00", usize::MAX);
+        for state in ["37x36:00", "2x1000:00", huge.as_str()] {
+            assert!(Board::from_state(state).is_err());
+        }
+    }
+}
@@ -1,23 +1,53 @@
 use std::{
     fs::File,
-    io::{BufReader, BufWriter},
+    io::BufReader,
     path::{Path, PathBuf},
 };
 
 use serde::{Deserialize, Serialize};
 
-use crate::{error::Error, size::Size};
+use crate::{error::Error, file, size::Size, stats::storage::StorageKind};
+
+/// The smallest allowed width of the histogram buckets in seconds
+const MIN_BUCKET: f64 = 0.01;
+/// The largest allowed width of the histogram buckets in seconds
+const MAX_BUCKET: f64 = 3600.;
+/// The largest allowed undo penalty in seconds
+const MAX_UNDO_PENALTY: f64 = 3600.;
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Config {
     #[serde(default)]
     pub default_size: Size,
+    /// Whether undo is allowed during timed solves
+    #[serde(default)]
+    pub allow_undo: bool,
+    /// Time penalty in seconds added for each undo
+    #[serde(default)]
+    pub undo_penalty: f64,
+    /// Width of the stats histogram buckets in seconds, chosen
+    /// automatically when zero
+    #[serde(default)]
+    pub histogram_bucket: f64,
+    /// Storage the stats are saved in
+    #[serde(default)]
+    pub storage: StorageKind,
 }
 
 impl Config {
-    /// Loads config from default json file path
-    pub fn load() -> Self {
-        Self::from_json(Self::get_path()).unwrap_or_default()
+    /// Loads config from default json file path, default config is used
+    /// when the file doesn't exist
+    pub fn load() -> Result<Self, Error> {
+        let path = Self::get_path();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Self::from_json(&path).map_err(|e| {
+            Error::Msg(format!(
+                "invalid config file '{}': {e}",
+                path.display()
+            ))
+        })
     }
 
     /// Saves config to default json path
@@ -28,13 +58,36 @@ impl Config {
     /// Loads config from given path
     pub fn from_json(file: impl AsRef<Path>) -> Result<Self, Error> {
         let buffer = BufReader::new(File::open(file)?);
-        Ok(serde_json::from_reader(buffer)?)
+        let config: Self = serde_json::from_reader(buffer)?;
+        config.validate()?;
+        Ok(config)
     }
 
     /// Saves config to given path
-    pub fn to_json(&self, file: impl AsRef<Path>) -> Result<(), Error> {
-        let buffer = BufWriter::new(File::create(file)?);
-        Ok(serde_json::to_writer_pretty(buffer, self)?)
+    pub fn to_json(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let text = serde_json::to_string_pretty(self)?;
+        file::save(path.as_ref(), text.as_bytes())
+    }
+
+    /// Checks whether the values are in the allowed ranges
+    fn validate(&self) -> Result<(), Error> {
+        if !(0. ..=MAX_UNDO_PENALTY).contains(&self.undo_penalty) {
+            return Err(format!(
+                "undo_penalty has to be between 0 and {MAX_UNDO_PENALTY} \
+                seconds"
+            )
+            .into());
+        }
+
+        let bucket = self.histogram_bucket;
+        if bucket != 0. && !(MIN_BUCKET..=MAX_BUCKET).contains(&bucket) {
+            return Err(format!(
+                "histogram_bucket has to be 0 or between {MIN_BUCKET} and \
+                {MAX_BUCKET} seconds"
+            )
+            .into());
+        }
+        Ok(())
     }
 
     /// Gets config directory
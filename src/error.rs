@@ -4,6 +4,8 @@ use std::fmt::Display;
 pub enum Error {
     IO(std::io::Error),
     Serde(serde_json::Error),
+    #[cfg(feature = "sqlite")]
+    Sqlite(rusqlite::Error),
     Msg(String),
     Exit,
 }
@@ -20,6 +22,13 @@ impl From<serde_json::Error> for Error {
     }
 }
 
+#[cfg(feature = "sqlite")]
+impl From<rusqlite::Error> for Error {
+    fn from(value: rusqlite::Error) -> Self {
+        Self::Sqlite(value)
+    }
+}
+
 impl From<&str> for Error {
     fn from(value: &str) -> Self {
         Self::Msg(value.to_string())
@@ -37,6 +46,8 @@ impl Display for Error {
         match self {
             Error::IO(e) => write!(f, "{e}"),
             Error::Serde(e) => write!(f, "{e}"),
+            #[cfg(feature = "sqlite")]
+            Error::Sqlite(e) => write!(f, "{e}"),
             Error::Msg(msg) => write!(f, "{msg}"),
             Error::Exit => write!(f, "exit"),
         }
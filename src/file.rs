@@ -0,0 +1,157 @@
+use std::{
+    ffi::OsString,
+    fs::{copy, create_dir_all, read_to_string, rename, File, OpenOptions},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::error::Error;
+
+/// Number of backups kept of each saved file
+const BACKUPS: usize = 3;
+
+/// Reads the file on given path, [`None`] when the file doesn't exist
+pub fn read(path: &Path) -> Result<Option<String>, Error> {
+    match read_to_string(path) {
+        Ok(text) => Ok(Some(text)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Saves contents to the file on given path. Contents are written to the
+/// temporary file, which then replaces the file, so the file is never left
+/// partially written. Previous versions of the file are kept as rotating
+/// backups `<file>.1` (the newest) to `<file>.3`.
+pub fn save(path: &Path, contents: &[u8]) -> Result<(), Error> {
+    if let Some(dir) = path.parent() {
+        create_dir_all(dir)?;
+    }
+
+    let tmp = with_suffix(path, "tmp");
+    let mut file = File::create(&tmp)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    drop(file);
+
+    backup(path)?;
+    rename(&tmp, path)?;
+    sync_dir(path)
+}
+
+/// Locks the file on given path for exclusive access by this process until
+/// the returned lock file is dropped. Separate `<file>.lock` file is locked,
+/// because the file itself is replaced on save.
+pub fn lock(path: &Path) -> Result<File, Error> {
+    if let Some(dir) = path.parent() {
+        create_dir_all(dir)?;
+    }
+
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(with_suffix(path, "lock"))?;
+    file.lock()?;
+    Ok(file)
+}
+
+/// Syncs the directory containing the file on given path, so the rename of
+/// the file isn't lost on power failure
+#[cfg(unix)]
+fn sync_dir(path: &Path) -> Result<(), Error> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    File::open(dir)?.sync_all()?;
+    Ok(())
+}
+
+/// Directories can't be opened for syncing on other platforms
+#[cfg(not(unix))]
+fn sync_dir(_path: &Path) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Rotates backups of the file on given path and backs up the file
+fn backup(path: &Path) -> Result<(), Error> {
+    if !path.exists() {
+        return Ok(());
+    }
+
+    for i in (1..BACKUPS).rev() {
+        let old = with_suffix(path, &i.to_string());
+        if old.exists() {
+            rename(&old, with_suffix(path, &(i + 1).to_string()))?;
+        }
+    }
+    copy(path, with_suffix(path, "1"))?;
+    Ok(())
+}
+
+/// Gets the path with given suffix appended to the file name
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().map(OsString::from).unwrap_or_default();
+    name.push(".");
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::remove_dir_all;
+
+    use crate::test_util::test_dir;
+
+    use super::*;
+
+    #[test]
+    fn save_creates_file() {
+        let dir = test_dir("file-create");
+        let path = dir.join("nested/3x3.json");
+        save(&path, b"first").unwrap();
+
+        assert_eq!(read(&path).unwrap().as_deref(), Some("first"));
+        assert!(!with_suffix(&path, "tmp").exists());
+        assert!(!with_suffix(&path, "1").exists());
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn save_rotates_backups() {
+        let dir = test_dir("file-backups");
+        let path = dir.join("3x3.json");
+        for i in 0..5 {
+            save(&path, i.to_string().as_bytes()).unwrap();
+        }
+
+        assert_eq!(read(&path).unwrap().as_deref(), Some("4"));
+        for i in 1..=BACKUPS {
+            let backup = read(&with_suffix(&path, &i.to_string())).unwrap();
+            assert_eq!(backup, Some((4 - i).to_string()));
+        }
+        assert!(!with_suffix(&path, "4").exists());
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn read_missing_file() {
+        let dir = test_dir("file-missing");
+        assert_eq!(read(&dir.join("4x4.json")).unwrap(), None);
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn lock_is_exclusive() {
+        let dir = test_dir("file-lock");
+        let path = dir.join("3x3.json");
+        let lock = lock(&path).unwrap();
+
+        let other = File::open(with_suffix(&path, "lock")).unwrap();
+        assert!(other.try_lock().is_err());
+        drop(lock);
+        assert!(other.try_lock().is_ok());
+        remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,16 +1,24 @@
 use std::{
     env,
-    fs::create_dir_all,
-    io::{stdout, Write},
+    fs::{create_dir_all, read_to_string, File},
+    io::{stdout, BufWriter, ErrorKind, Write},
     panic::{set_hook, take_hook},
     process::{Command, ExitCode},
 };
 
 use app::App;
-use args::{Action, Args};
+use args::{Action, Args, SessionAction};
+use board::{board_struct::Board, notation, rotation::Rotation};
 use config::Config;
 use crossterm::terminal::{disable_raw_mode, is_raw_mode_enabled};
 use error::Error;
+use size::Size;
+use stats::{
+    export::{self, ExportFilter},
+    import::Import,
+    session::SessionChange,
+    storage,
+};
 use termint::{enums::Color, widgets::StrSpanExtension};
 
 mod app;
@@ -18,10 +26,17 @@ mod args;
 mod board;
 mod config;
 mod error;
+mod file;
 mod size;
+mod solver;
 mod stats;
+#[cfg(test)]
+mod test_util;
 mod tui;
 
+/// Number of the imported solves shown in the dry run
+const PREVIEW_LEN: usize = 10;
+
 fn main() -> ExitCode {
     match run() {
         Ok(_) => ExitCode::SUCCESS,
@@ -36,8 +51,13 @@ fn run() -> Result<(), Error> {
     register_panic_hook();
 
     let args = Args::parse(std::env::args())?;
-    match args.action {
+    match args.action.clone() {
         Action::Play => run_play(args),
+        Action::Solve(state) => run_solve(args, state),
+        Action::Convert(moves) => run_convert(args, moves),
+        Action::Session(action) => run_session(args, action),
+        Action::Export => run_export(args),
+        Action::Import(file) => run_import(args, file),
         Action::Config => run_config(),
         Action::Help => {
             Args::help();
@@ -47,13 +67,179 @@ fn run() -> Result<(), Error> {
 }
 
 fn run_play(args: Args) -> Result<(), Error> {
-    let config = Config::load();
-    let size = args.size.unwrap_or(config.default_size);
+    let config = Config::load()?;
+    let scramble = args
+        .scramble
+        .as_deref()
+        .map(Board::from_state)
+        .transpose()?;
+    let size = match (&scramble, args.size) {
+        (Some(board), Some(size)) if board.size != size.into() => {
+            return Err(format!(
+                "scramble is for {}x{} board, but the size is {}x{}",
+                board.size.x, board.size.y, size.width, size.height
+            )
+            .into());
+        }
+        (Some(board), _) => board.size,
+        (None, size) => size.unwrap_or(config.default_size).into(),
+    };
 
-    let mut app = App::new(size.into())?;
+    let mut app = App::new(size, config)?;
+    app.next_seed = args.seed;
+    if let Some(board) = scramble {
+        app.load_scramble(board);
+    }
     app.run()
 }
 
+fn run_solve(args: Args, state: String) -> Result<(), Error> {
+    let size = args.size.unwrap_or(Config::load()?.default_size);
+    let board = Board::parse(&state, size.into())?;
+
+    let rots = solver::solve(&board, args.budget)?;
+    if rots.is_empty() {
+        println!("Board is already solved");
+        return Ok(());
+    }
+
+    let moves = match args.absolute {
+        true => notation::format_absolute(&rots),
+        false => Rotation::to_moves(&rots, board.selected, board.size),
+    };
+    println!(
+        "{} {moves}",
+        format!("{} moves:", rots.len()).fg(Color::Green)
+    );
+    Ok(())
+}
+
+fn run_convert(args: Args, moves: String) -> Result<(), Error> {
+    let size = args.size.unwrap_or(Config::load()?.default_size);
+    let cursor = args.cursor.unwrap_or(Size::new(0, 0));
+    if cursor.width >= size.width || cursor.height >= size.height {
+        return Err(format!(
+            "cursor {} {} is outside of the {}x{} board",
+            cursor.width, cursor.height, size.width, size.height
+        )
+        .into());
+    }
+    let (size, cursor) = (size.into(), cursor.into());
+
+    let converted = match notation::is_absolute(&moves) {
+        true => notation::to_cursor(&moves, cursor, size)?,
+        false => notation::to_absolute(&moves, cursor, size)?,
+    };
+    println!("{converted}");
+    Ok(())
+}
+
+fn run_session(args: Args, action: SessionAction) -> Result<(), Error> {
+    let config = Config::load()?;
+    let size = args.size.unwrap_or(config.default_size).into();
+    let storage = storage::open(config.storage)?;
+    let mut stats = storage.load(&size)?;
+
+    let change = match action {
+        SessionAction::List => {
+            for session in stats.sessions() {
+                let active = session.name() == stats.session().name();
+                println!(
+                    "{} {} {}",
+                    if active { "*" } else { " " },
+                    session.name().fg(Color::Green),
+                    format!(
+                        "({} solves)",
+                        storage.count(&size, session.name())?
+                    )
+                    .fg(Color::Gray),
+                );
+            }
+            return Ok(());
+        }
+        SessionAction::New(name) => SessionChange::New(name),
+        SessionAction::Rename(old, new) => SessionChange::Rename(old, new),
+        SessionAction::Switch(name) => SessionChange::Switch(name),
+        SessionAction::Merge(from, into) => SessionChange::Merge(from, into),
+    };
+    storage.change_session(&size, &mut stats, &change)
+}
+
+fn run_export(args: Args) -> Result<(), Error> {
+    let filter = ExportFilter {
+        size: args.size.map(|s| s.into()),
+        from: args.from,
+        to: args.to,
+    };
+    let storage = storage::open(Config::load()?.storage)?;
+
+    match args.output {
+        Some(path) => {
+            let mut file = BufWriter::new(File::create(&path)?);
+            let cnt =
+                export::export(&mut file, &*storage, args.format, &filter)?;
+            file.flush()?;
+            println!("Exported {cnt} solves to {path}");
+        }
+        None => {
+            let res = export::export(
+                &mut stdout().lock(),
+                &*storage,
+                args.format,
+                &filter,
+            );
+            match res {
+                // Output piped to a program which stopped reading
+                Err(Error::IO(e)) if e.kind() == ErrorKind::BrokenPipe => {}
+                res => _ = res?,
+            }
+        }
+    }
+    Ok(())
+}
+
+fn run_import(args: Args, file: String) -> Result<(), Error> {
+    let config = Config::load()?;
+    let size = args.size.unwrap_or(config.default_size).into();
+    let storage = storage::open(config.storage)?;
+    let import = Import::parse(&read_to_string(file)?, size)?;
+    println!("Importing {} export", import.format);
+
+    for (size, solves) in import.solves {
+        let mut stats = storage.load(&size)?;
+        let cnt = solves.len();
+        let added = match args.dry_run {
+            true => stats.merge(solves),
+            false => storage.merge_solves(&size, &mut stats, solves)?,
+        };
+        println!(
+            "{} {} new solves, {} duplicates skipped",
+            format!("{}x{}:", size.x, size.y).fg(Color::Green),
+            added.len(),
+            cnt - added.len(),
+        );
+
+        if args.dry_run {
+            for stat in added.iter().take(PREVIEW_LEN) {
+                println!(
+                    "  {} {:>10} {}",
+                    stat.date().format("%d/%m/%Y %H:%M:%S"),
+                    stat.format_time(),
+                    stat.metrics().mtm,
+                );
+            }
+            if added.len() > PREVIEW_LEN {
+                println!("  ...");
+            }
+        }
+    }
+
+    if args.dry_run {
+        println!("{}", "Dry run, nothing was saved".fg(Color::Yellow));
+    }
+    Ok(())
+}
+
 fn run_config() -> Result<(), Error> {
     let editor = env::var("EDITOR").unwrap_or("vi".to_string());
     create_dir_all(Config::get_dir())?;
@@ -0,0 +1,244 @@
+use termint::geometry::Vec2;
+
+use crate::{
+    board::{
+        board_struct::{odd_permutation, Board},
+        rotation::Rotation,
+    },
+    error::Error,
+};
+
+/// Solves the board of any size by placing the cells row by row and then
+/// solving the last row using commutators. The solution isn't optimal.
+pub fn solve(board: &Board) -> Result<Vec<Rotation>, Error> {
+    let mut solver = Solver {
+        cells: board.cells.clone(),
+        size: board.size,
+        rots: Vec::new(),
+    };
+
+    solver.fix_parity()?;
+    for y in 0..solver.size.y - 1 {
+        for x in 0..solver.size.x {
+            solver.place(x, y);
+        }
+    }
+    solver.solve_last_row()?;
+
+    Ok(simplify(solver.rots, board.size))
+}
+
+/// Heuristic solver state
+struct Solver {
+    cells: Vec<usize>,
+    size: Vec2,
+    rots: Vec<Rotation>,
+}
+
+impl Solver {
+    /// Applies given rotation `cnt` times
+    fn rotate(&mut self, rot: Rotation, cnt: usize) {
+        for _ in 0..cnt {
+            rot.apply(&mut self.cells, self.size);
+            self.rots.push(rot);
+        }
+    }
+
+    /// Gets position of the cell with given value
+    fn find(&self, val: usize) -> Vec2 {
+        let id = self.cells.iter().position(|c| *c == val).unwrap_or(0);
+        Vec2::new(id % self.size.x, id / self.size.x)
+    }
+
+    /// Checks whether the permutation of cells is odd
+    fn odd(&self) -> bool {
+        odd_permutation(&self.cells)
+    }
+
+    /// When the width is odd, row rotations and placing the cells keep the
+    /// parity, so odd permutation has to be fixed by column rotation
+    fn fix_parity(&mut self) -> Result<(), Error> {
+        if self.size.x.is_multiple_of(2) || !self.odd() {
+            return Ok(());
+        }
+
+        if self.size.y.is_multiple_of(2) {
+            self.rotate(Rotation::Down(0), 1);
+            Ok(())
+        } else {
+            Err(Error::Msg("board state is not solvable".into()))
+        }
+    }
+
+    /// Places cell belonging to the given position, expecting all the
+    /// previous positions to be solved
+    fn place(&mut self, tx: usize, ty: usize) {
+        let val = tx + ty * self.size.x + 1;
+        loop {
+            let pos = self.find(val);
+            if pos.x == tx && pos.y == ty {
+                return;
+            }
+
+            if pos.y == ty {
+                // Moves the cell out of the row without breaking solved
+                self.rotate(Rotation::Down(pos.x), 1);
+                self.rotate(Rotation::Right(ty + 1), 1);
+                self.rotate(Rotation::Up(pos.x), 1);
+            } else if pos.x == tx {
+                self.rotate(Rotation::Right(pos.y), 1);
+            } else {
+                let dist = pos.y - ty;
+                let shift = (tx + self.size.x - pos.x) % self.size.x;
+                self.rotate(Rotation::Down(tx), dist);
+                self.rotate(Rotation::Right(pos.y), shift);
+                self.rotate(Rotation::Up(tx), dist);
+            }
+        }
+    }
+
+    /// Solves the last row using 3-cycles, expecting all the other rows to
+    /// be solved
+    fn solve_last_row(&mut self) -> Result<(), Error> {
+        let w = self.size.x;
+        let y = self.size.y - 1;
+        if self.odd() {
+            self.rotate(Rotation::Right(y), 1);
+        }
+
+        for x in 0..w.saturating_sub(2) {
+            let pos = self.find(x + y * w + 1);
+            if pos.x == x {
+                continue;
+            }
+            let third = (x + 1..w).find(|c| *c != pos.x).unwrap_or(x);
+            self.cycle(pos.x, x, third);
+        }
+
+        match self.cells.windows(2).all(|c| c[0] < c[1]) {
+            true => Ok(()),
+            false => Err(Error::Msg("board state is not solvable".into())),
+        }
+    }
+
+    /// Cycles three cells in the last row, moving cell from `a` to `b`,
+    /// from `b` to `c` and from `c` to `a`
+    fn cycle(&mut self, a: usize, b: usize, c: usize) {
+        let w = self.size.x;
+        let y = self.size.y - 1;
+        let k = (a + w - b) % w;
+        let j = (a + w - c) % w;
+
+        self.rotate(Rotation::Down(a), 1);
+        self.rotate(Rotation::Right(y), k);
+        self.rotate(Rotation::Up(a), 1);
+        self.rotate(Rotation::Left(y), k);
+        self.rotate(Rotation::Right(y), j);
+        self.rotate(Rotation::Down(a), 1);
+        self.rotate(Rotation::Left(y), j);
+        self.rotate(Rotation::Up(a), 1);
+    }
+}
+
+/// Simplifies rotations by merging consecutive rotations of the same line
+/// and using the shorter direction
+fn simplify(rots: Vec<Rotation>, size: Vec2) -> Vec<Rotation> {
+    // Line is represented as (is column, index) with its forward shift
+    let mut lines: Vec<((bool, usize), usize)> = Vec::new();
+    for rot in rots {
+        let (line, fwd) = match rot {
+            Rotation::Up(x) => ((true, x), false),
+            Rotation::Down(x) => ((true, x), true),
+            Rotation::Left(y) => ((false, y), false),
+            Rotation::Right(y) => ((false, y), true),
+        };
+        let len = if line.0 { size.y } else { size.x };
+        let shift = if fwd { 1 } else { len - 1 };
+
+        match lines.last_mut() {
+            Some((l, s)) if *l == line => {
+                *s = (*s + shift) % len;
+                if *s == 0 {
+                    lines.pop();
+                }
+            }
+            _ => lines.push((line, shift)),
+        }
+    }
+
+    let mut res = Vec::new();
+    for ((col, id), shift) in lines {
+        let len = if col { size.y } else { size.x };
+        let (fwd, back) = match col {
+            true => (Rotation::Down(id), Rotation::Up(id)),
+            false => (Rotation::Right(id), Rotation::Left(id)),
+        };
+        if shift <= len - shift {
+            res.extend(std::iter::repeat_n(fwd, shift));
+        } else {
+            res.extend(std::iter::repeat_n(back, len - shift));
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that the rotations solve the board
+    fn solves(board: &Board, rots: &[Rotation]) -> bool {
+        let mut board = board.clone();
+        for rot in rots {
+            board.rotate(*rot);
+        }
+        board.solved()
+    }
+
+    #[test]
+    fn solves_scrambles() {
+        for (w, h) in [(4, 4), (5, 5), (4, 5), (5, 4), (6, 3), (2, 7)] {
+            for seed in 0..5 {
+                let mut board = Board::new(Vec2::new(w, h));
+                board.scramble(seed);
+                let rots = solve(&board).unwrap();
+                assert!(solves(&board, &rots), "{w}x{h} seed {seed}");
+            }
+        }
+    }
+
+    /// Odd permutation of the board with odd width has to be fixed by the
+    /// column rotation
+    #[test]
+    fn fixes_parity() {
+        let mut board = Board::new(Vec2::new(5, 4));
+        board.cells.swap(0, 1);
+        assert!(board.odd());
+        let rots = solve(&board).unwrap();
+        assert!(solves(&board, &rots));
+    }
+
+    #[test]
+    fn odd_by_odd_parity() {
+        let mut board = Board::new(Vec2::new(5, 5));
+        board.cells.swap(3, 17);
+        assert!(solve(&board).is_err());
+    }
+
+    #[test]
+    fn simplify_merges_rotations() {
+        let size = Vec2::new(5, 4);
+        let rots = vec![
+            Rotation::Right(1),
+            Rotation::Right(1),
+            Rotation::Right(1),
+            Rotation::Down(0),
+            Rotation::Up(0),
+            Rotation::Up(2),
+        ];
+        assert_eq!(
+            simplify(rots, size),
+            [Rotation::Left(1), Rotation::Left(1), Rotation::Up(2)]
+        );
+    }
+}
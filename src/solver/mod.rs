@@ -0,0 +1,61 @@
+use termint::geometry::Vec2;
+
+use crate::{
+    board::{board_struct::Board, rotation::Rotation},
+    error::Error,
+};
+
+pub mod heuristic;
+pub mod optimal;
+
+/// Finds solution of the given board, returning rotations solving it. Uses
+/// optimal solver for small boards and heuristic solver otherwise. Fails
+/// when the solution has more moves than the given budget.
+pub fn solve(
+    board: &Board,
+    budget: Option<usize>,
+) -> Result<Vec<Rotation>, Error> {
+    let rots = match board.size.x * board.size.y {
+        n if n <= optimal::MAX_CELLS => optimal::solve(board)?,
+        _ => heuristic::solve(board)?,
+    };
+
+    if let Some(budget) = budget.filter(|b| rots.len() > *b) {
+        return Err(Error::Msg(format!(
+            "solution has {} moves, which exceeds the budget of {budget}",
+            rots.len()
+        )));
+    }
+
+    verify(board, &rots)?;
+    Ok(rots)
+}
+
+/// Verifies the solution by replaying it on the copy of the board
+fn verify(board: &Board, rots: &[Rotation]) -> Result<(), Error> {
+    let mut board = board.clone();
+    board.span = Vec2::new(1, 1);
+    let moves = Rotation::to_moves(rots, board.selected, board.size);
+    for c in moves.chars().filter(|c| !c.is_whitespace()) {
+        board.apply_move(c)?;
+    }
+
+    match board.solved() {
+        true => Ok(()),
+        false => Err(Error::Msg("solver produced invalid solution".into())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solution_exceeds_budget() {
+        let mut board = Board::new(Vec2::new(4, 4));
+        board.scramble(7);
+        let rots = solve(&board, None).unwrap();
+        assert!(solve(&board, Some(rots.len())).is_ok());
+        assert!(solve(&board, Some(rots.len() - 1)).is_err());
+    }
+}
@@ -0,0 +1,138 @@
+use std::collections::{hash_map::Entry, HashMap, VecDeque};
+
+use crate::{
+    board::{board_struct::Board, rotation::Rotation},
+    error::Error,
+};
+
+/// Maximum number of cells of the board the optimal solver supports
+pub const MAX_CELLS: usize = 9;
+
+/// Finds the shortest sequence of rotations solving the given board using
+/// breadth first search
+pub fn solve(board: &Board) -> Result<Vec<Rotation>, Error> {
+    let cnt = board.size.x * board.size.y;
+    if cnt > MAX_CELLS {
+        return Err(Error::Msg(format!(
+            "optimal solver supports boards with at most {MAX_CELLS} cells"
+        )));
+    }
+
+    let start = encode(&board.cells);
+    let goal = encode(&(1..=cnt).collect::<Vec<_>>());
+    let rots = Rotation::all(board.size);
+
+    let mut parents: HashMap<u64, (u64, Rotation)> = HashMap::new();
+    let mut queue = VecDeque::from([start]);
+    let mut cells = vec![0; cnt];
+    while let Some(state) = queue.pop_front() {
+        if state == goal {
+            return Ok(path(&parents, start, goal));
+        }
+
+        for rot in &rots {
+            decode(state, &mut cells);
+            rot.apply(&mut cells, board.size);
+            let next = encode(&cells);
+            if next == start {
+                continue;
+            }
+            if let Entry::Vacant(e) = parents.entry(next) {
+                e.insert((state, *rot));
+                queue.push_back(next);
+            }
+        }
+    }
+    Err(Error::Msg("board state is not solvable".into()))
+}
+
+/// Reconstructs rotations leading from start state to the end state
+fn path(
+    parents: &HashMap<u64, (u64, Rotation)>,
+    start: u64,
+    mut state: u64,
+) -> Vec<Rotation> {
+    let mut rots = Vec::new();
+    while state != start {
+        let (prev, rot) = parents[&state];
+        rots.push(rot);
+        state = prev;
+    }
+    rots.reverse();
+    rots
+}
+
+/// Encodes cells to the number, each cell taking 4 bits
+fn encode(cells: &[usize]) -> u64 {
+    cells
+        .iter()
+        .rev()
+        .fold(0, |acc, c| (acc << 4) | (*c as u64 - 1))
+}
+
+/// Decodes cells from the number encoded using [`encode`]
+fn decode(mut state: u64, cells: &mut [usize]) {
+    for cell in cells.iter_mut() {
+        *cell = (state & 0xf) as usize + 1;
+        state >>= 4;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use termint::geometry::Vec2;
+
+    use super::*;
+
+    /// Creates the board by applying given rotations to the solved board
+    fn rotated(size: Vec2, rots: &[Rotation]) -> Board {
+        let mut board = Board::new(size);
+        for rot in rots {
+            board.rotate(*rot);
+        }
+        board
+    }
+
+    /// Checks that the rotations solve the board
+    fn solves(board: &Board, rots: &[Rotation]) -> bool {
+        let mut board = board.clone();
+        for rot in rots {
+            board.rotate(*rot);
+        }
+        board.solved()
+    }
+
+    #[test]
+    fn solved_board_needs_no_moves() {
+        let board = Board::new(Vec2::new(3, 3));
+        assert!(solve(&board).unwrap().is_empty());
+    }
+
+    #[test]
+    fn finds_shortest_solution() {
+        let scramble =
+            [Rotation::Right(0), Rotation::Down(2), Rotation::Up(1)];
+        let board = rotated(Vec2::new(3, 3), &scramble);
+        let rots = solve(&board).unwrap();
+        assert_eq!(rots.len(), 3);
+        assert!(solves(&board, &rots));
+
+        let board = rotated(Vec2::new(3, 2), &[Rotation::Left(1)]);
+        assert_eq!(solve(&board).unwrap(), [Rotation::Right(1)]);
+    }
+
+    #[test]
+    fn rejects_large_board() {
+        assert!(solve(&Board::new(Vec2::new(2, 5))).is_err());
+    }
+
+    /// Rotations of odd lines are even permutations, so odd permutation
+    /// of the odd by odd board can't be solved
+    #[test]
+    fn odd_by_odd_parity() {
+        let mut board = Board::new(Vec2::new(3, 3));
+        board.cells.swap(0, 1);
+        assert!(!board.reachable());
+        assert!(solve(&board).is_err());
+    }
+}
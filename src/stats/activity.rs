@@ -0,0 +1,137 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Local, NaiveDate, Utc};
+
+use super::storage::Storage;
+
+/// Number of solves on each day across all the board sizes. Days are in the
+/// local time zone.
+#[derive(Debug, Clone, Default)]
+pub struct Activity {
+    days: BTreeMap<NaiveDate, usize>,
+}
+
+impl Activity {
+    /// Loads the activity from the solves of all the sizes in given storage.
+    /// Sizes with unreadable stats are skipped, so a broken file of other
+    /// size doesn't prevent playing.
+    pub fn load(storage: &dyn Storage) -> Self {
+        let mut activity = Self::default();
+        let sizes = storage.sizes().unwrap_or_default();
+        for dates in sizes.iter().filter_map(|s| storage.dates(s).ok()) {
+            for date in dates {
+                activity.add(date);
+            }
+        }
+        activity
+    }
+
+    /// Adds solve finished on given date
+    pub fn add(&mut self, date: DateTime<Utc>) {
+        *self.days.entry(local_day(date)).or_default() += 1;
+    }
+
+    /// Removes solve finished on given date
+    pub fn remove(&mut self, date: DateTime<Utc>) {
+        let day = local_day(date);
+        if let Some(cnt) = self.days.get_mut(&day) {
+            *cnt -= 1;
+            if *cnt == 0 {
+                self.days.remove(&day);
+            }
+        }
+    }
+
+    /// Gets number of solves on given day
+    pub fn solves(&self, day: NaiveDate) -> usize {
+        self.days.get(&day).copied().unwrap_or_default()
+    }
+
+    /// Gets the highest number of solves on a single day
+    pub fn max(&self) -> usize {
+        self.days.values().copied().max().unwrap_or_default()
+    }
+
+    /// Gets number of days with at least one solve
+    pub fn active_days(&self) -> usize {
+        self.days.len()
+    }
+
+    /// Gets number of consecutive days with solves ending with given day.
+    /// Streak isn't broken before the end of the day, so when there are no
+    /// solves on the given day, the streak can end on the previous day.
+    pub fn current_streak(&self, today: NaiveDate) -> usize {
+        let mut day = match self.days.contains_key(&today) {
+            true => today,
+            false => today.pred_opt().unwrap_or(today),
+        };
+
+        let mut streak = 0;
+        while self.days.contains_key(&day) {
+            streak += 1;
+            let Some(prev) = day.pred_opt() else {
+                break;
+            };
+            day = prev;
+        }
+        streak
+    }
+
+    /// Gets the longest number of consecutive days with solves
+    pub fn longest_streak(&self) -> usize {
+        let mut longest = 0;
+        let mut streak = 0;
+        let mut last: Option<NaiveDate> = None;
+        for day in self.days.keys() {
+            streak = match last.and_then(|l| l.succ_opt()) {
+                Some(next) if next == *day => streak + 1,
+                _ => 1,
+            };
+            longest = longest.max(streak);
+            last = Some(*day);
+        }
+        longest
+    }
+}
+
+/// Gets the current day in the local time zone
+pub fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+/// Gets the day of given date in the local time zone
+fn local_day(date: DateTime<Utc>) -> NaiveDate {
+    date.with_timezone(&Local).date_naive()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs::{remove_dir_all, write},
+        time::Duration,
+    };
+
+    use termint::geometry::Vec2;
+
+    use crate::{
+        stats::storage::json::JsonStorage,
+        test_util::{date, solve, test_dir},
+    };
+
+    use super::*;
+
+    #[test]
+    fn broken_size_is_skipped() {
+        let dir = test_dir("activity-broken");
+        let storage = JsonStorage::with_dir(dir.clone());
+        let size = Vec2::new(3, 3);
+        let mut stats = storage.load(&size).unwrap();
+        let stat = solve(Duration::from_secs(5)).with_date(date(0));
+        storage.add_solve(&size, &mut stats, stat).unwrap();
+        write(dir.join("4x4.json"), r#"{"version": 99}"#).unwrap();
+
+        let activity = Activity::load(&storage);
+        assert_eq!(activity.solves(local_day(date(0))), 1);
+        remove_dir_all(dir).unwrap();
+    }
+}
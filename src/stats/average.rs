@@ -0,0 +1,163 @@
+use std::{fmt::Display, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+/// Rolling average over the most recent solves
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Average {
+    /// Mean of 3
+    Mo3,
+    /// Average of 5
+    Ao5,
+    /// Average of 12
+    Ao12,
+    /// Average of 50
+    Ao50,
+    /// Average of 100
+    Ao100,
+}
+
+impl Average {
+    /// All the supported averages
+    pub const ALL: [Average; 5] = [
+        Average::Mo3,
+        Average::Ao5,
+        Average::Ao12,
+        Average::Ao50,
+        Average::Ao100,
+    ];
+
+    /// Gets number of solves in the average
+    pub fn count(&self) -> usize {
+        match self {
+            Average::Mo3 => 3,
+            Average::Ao5 => 5,
+            Average::Ao12 => 12,
+            Average::Ao50 => 50,
+            Average::Ao100 => 100,
+        }
+    }
+
+    /// Gets number of the best and the worst solves removed from the
+    /// average. It's 5% of the solves rounded up, mean removes none.
+    pub fn trim(&self) -> usize {
+        match self {
+            Average::Mo3 => 0,
+            avg => avg.count().div_ceil(20),
+        }
+    }
+
+    /// Computes the average of the first [`Average::count`] given times,
+    /// where [`None`] time is DNF. Returns [`None`] when there are not
+    /// enough times and `Some(None)` when the average is DNF, which happens
+    /// when there are more DNFs than the removed worst solves.
+    pub fn compute<I>(&self, times: I) -> Option<Option<Duration>>
+    where
+        I: IntoIterator<Item = Option<Duration>>,
+    {
+        let times: Vec<Option<Duration>> =
+            times.into_iter().take(self.count()).collect();
+        if times.len() < self.count() {
+            return None;
+        }
+
+        let mut times: Vec<Duration> = times.into_iter().flatten().collect();
+        if times.len() < self.count() - self.trim() {
+            return Some(None);
+        }
+
+        times.sort_unstable();
+        times.truncate(self.count() - self.trim());
+        let counted = &times[self.trim()..];
+        Some(Some(
+            counted.iter().sum::<Duration>() / counted.len() as u32,
+        ))
+    }
+}
+
+impl Display for Average {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Average::Mo3 => f.pad("mo3"),
+            avg => f.pad(&format!("ao{}", avg.count())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        stats::stat::{Penalty, Stat},
+        test_util::solve,
+    };
+
+    use super::*;
+
+    /// Creates times in seconds, negative time is DNF
+    fn times(secs: &[f64]) -> Vec<Option<Duration>> {
+        secs.iter()
+            .map(|s| (*s >= 0.).then(|| Duration::from_secs_f64(*s)))
+            .collect()
+    }
+
+    #[test]
+    fn trims_best_and_worst() {
+        assert_eq!(Average::Ao5.trim(), 1);
+        assert_eq!(Average::Ao12.trim(), 1);
+        assert_eq!(Average::Ao50.trim(), 3);
+        assert_eq!(Average::Ao100.trim(), 5);
+
+        let avg = Average::Ao5.compute(times(&[5., 1., 3., 9., 4.]));
+        assert_eq!(avg, Some(Some(Duration::from_secs(4))));
+    }
+
+    #[test]
+    fn mean_keeps_all() {
+        let avg = Average::Mo3.compute(times(&[1., 2., 6.]));
+        assert_eq!(avg, Some(Some(Duration::from_secs(3))));
+        assert_eq!(Average::Mo3.compute(times(&[1., -1., 6.])), Some(None));
+    }
+
+    #[test]
+    fn not_enough_solves() {
+        assert_eq!(Average::Ao5.compute(times(&[1., 2., 3., 4.])), None);
+    }
+
+    /// Only the first [`Average::count`] times are used
+    #[test]
+    fn uses_latest_times() {
+        let avg = Average::Ao5.compute(times(&[2., 2., 2., 2., 2., 100.]));
+        assert_eq!(avg, Some(Some(Duration::from_secs(2))));
+    }
+
+    #[test]
+    fn dnf_is_worst() {
+        let avg = Average::Ao5.compute(times(&[5., -1., 3., 9., 4.]));
+        assert_eq!(avg, Some(Some(Duration::from_secs(6))));
+        let avg = Average::Ao5.compute(times(&[5., -1., 3., -1., 4.]));
+        assert_eq!(avg, Some(None));
+
+        let mut secs = [10.; 12];
+        secs[3] = -1.;
+        let avg = Average::Ao12.compute(times(&secs));
+        assert_eq!(avg, Some(Some(Duration::from_secs(10))));
+        secs[7] = -1.;
+        assert_eq!(Average::Ao12.compute(times(&secs)), Some(None));
+    }
+
+    #[test]
+    fn penalties_are_counted() {
+        let solves = [
+            (3, Penalty::PlusTwo),
+            (4, Penalty::None),
+            (1, Penalty::Dnf),
+            (6, Penalty::None),
+            (2, Penalty::PlusTwo),
+        ]
+        .map(|(secs, p)| solve(Duration::from_secs(secs)).with_penalty(p));
+        // 5, 4, DNF, 6, 4 trims 4 and DNF
+        let avg = Average::Ao5.compute(solves.iter().map(Stat::final_time));
+        assert_eq!(avg, Some(Some(Duration::from_secs(5))));
+    }
+}
@@ -0,0 +1,140 @@
+use std::time::Duration;
+
+use super::stat::Stat;
+
+/// Distribution of the solve times, DNF solves are not included
+#[derive(Debug, Clone)]
+pub struct Distribution {
+    /// Final times of the solves sorted from the fastest
+    times: Vec<Duration>,
+}
+
+impl Distribution {
+    /// Creates new [`Distribution`] of the final times of given solves
+    pub fn new<'a, I>(solves: I) -> Self
+    where
+        I: IntoIterator<Item = &'a Stat>,
+    {
+        let mut times: Vec<Duration> =
+            solves.into_iter().filter_map(|s| s.final_time()).collect();
+        times.sort();
+        Self { times }
+    }
+
+    /// Gets number of the times
+    pub fn len(&self) -> usize {
+        self.times.len()
+    }
+
+    /// Checks whether there are no times
+    pub fn is_empty(&self) -> bool {
+        self.times.is_empty()
+    }
+
+    /// Gets the fastest time
+    pub fn min(&self) -> Option<Duration> {
+        self.times.first().copied()
+    }
+
+    /// Gets the slowest time
+    pub fn max(&self) -> Option<Duration> {
+        self.times.last().copied()
+    }
+
+    /// Gets the mean of the times
+    pub fn mean(&self) -> Option<Duration> {
+        if self.is_empty() {
+            return None;
+        }
+        Some(self.times.iter().sum::<Duration>() / self.len() as u32)
+    }
+
+    /// Gets the sample standard deviation of the times, [`None`] when there
+    /// are less than two times
+    pub fn std_dev(&self) -> Option<Duration> {
+        if self.len() < 2 {
+            return None;
+        }
+
+        let mean = self.mean()?.as_secs_f64();
+        let sum: f64 = self
+            .times
+            .iter()
+            .map(|t| (t.as_secs_f64() - mean).powi(2))
+            .sum();
+        Some(Duration::from_secs_f64(
+            (sum / (self.len() - 1) as f64).sqrt(),
+        ))
+    }
+
+    /// Gets the median of the times
+    pub fn median(&self) -> Option<Duration> {
+        self.percentile(50.)
+    }
+
+    /// Gets given percentile of the times, interpolating between the two
+    /// closest times
+    pub fn percentile(&self, percent: f64) -> Option<Duration> {
+        let last = self.len().checked_sub(1)?;
+        let rank = percent.clamp(0., 100.) / 100. * last as f64;
+        let (low, high) = (
+            self.times[rank.floor() as usize],
+            self.times[rank.ceil() as usize],
+        );
+        Some(low + (high - low).mul_f64(rank.fract()))
+    }
+
+    /// Gets rounded bucket width splitting the times into about given number
+    /// of buckets
+    pub fn bucket_width(&self, buckets: usize) -> Duration {
+        const STEPS: [f64; 12] =
+            [0.01, 0.02, 0.05, 0.1, 0.2, 0.25, 0.5, 1., 2., 5., 10., 30.];
+
+        let (Some(min), Some(max)) = (self.min(), self.max()) else {
+            return Duration::from_secs(1);
+        };
+        let width = (max - min).as_secs_f64() / buckets.max(1) as f64;
+        let step = STEPS.iter().find(|s| **s >= width);
+        // Minutes are used for very long solves
+        Duration::from_secs_f64(*step.unwrap_or(&((width / 60.).ceil() * 60.)))
+    }
+
+    /// Gets number of the buckets of given width between the bucket with
+    /// the fastest time and the bucket with the slowest time
+    pub fn bucket_count(&self, bucket: Duration) -> usize {
+        let (Some(min), Some(max)) = (self.min(), self.max()) else {
+            return 0;
+        };
+        let bucket = bucket.as_secs_f64();
+        if bucket <= 0. {
+            return 1;
+        }
+
+        let start = (min.as_secs_f64() / bucket).floor();
+        let end = (max.as_secs_f64() / bucket).floor();
+        ((end - start) as usize).saturating_add(1)
+    }
+
+    /// Counts the times in the buckets of given width, starting with the
+    /// bucket containing the fastest time. Returns start of the first bucket
+    /// and the counts. [`Distribution::bucket_count`] should be checked
+    /// first, because all the buckets are allocated.
+    pub fn histogram(&self, bucket: Duration) -> (Duration, Vec<usize>) {
+        let Some(min) = self.min() else {
+            return (Duration::ZERO, vec![]);
+        };
+        let len = self.bucket_count(bucket);
+        let bucket = bucket.as_secs_f64();
+        if bucket <= 0. {
+            return (min, vec![self.len()]);
+        }
+
+        let start = (min.as_secs_f64() / bucket).floor();
+        let mut counts = vec![0; len];
+        for time in self.times.iter() {
+            let id = (time.as_secs_f64() / bucket).floor() - start;
+            counts[(id as usize).min(len - 1)] += 1;
+        }
+        (Duration::from_secs_f64(start * bucket), counts)
+    }
+}
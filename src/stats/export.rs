@@ -0,0 +1,314 @@
+use std::io::Write;
+
+use chrono::NaiveDate;
+use serde::Serialize;
+use termint::geometry::Vec2;
+
+use crate::error::Error;
+
+use super::{
+    sort::SortKey,
+    stat::{Penalty, Stat},
+    storage::Storage,
+};
+
+/// Number of the solves read from the storage at once
+const PAGE_LEN: usize = 1000;
+
+/// Format of the exported solves
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Jsonl,
+}
+
+impl TryFrom<&str> for ExportFormat {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "csv" => Ok(Self::Csv),
+            "jsonl" => Ok(Self::Jsonl),
+            _ => Err(format!("unknown export format: '{value}'").into()),
+        }
+    }
+}
+
+/// Filter of the exported solves
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ExportFilter {
+    /// Exports only solves of the board with given size
+    pub size: Option<Vec2>,
+    /// Exports only solves from this date (inclusive)
+    pub from: Option<NaiveDate>,
+    /// Exports only solves until this date (inclusive)
+    pub to: Option<NaiveDate>,
+}
+
+impl ExportFilter {
+    /// Checks whether given stat passes the date filter
+    fn matches(&self, stat: &Stat) -> bool {
+        let date = stat.date().date_naive();
+        self.from.is_none_or(|from| date >= from)
+            && self.to.is_none_or(|to| date <= to)
+    }
+}
+
+/// Single exported solve
+#[derive(Debug, Serialize)]
+struct Record<'a> {
+    size: String,
+    session: &'a str,
+    date: String,
+    time: f64,
+    penalty: Penalty,
+    moves_cnt: usize,
+    moves: &'a str,
+    end_x: usize,
+    end_y: usize,
+}
+
+/// Exports solves of all the sizes matching the filter in the given format,
+/// from the oldest solve of each size. Returns number of exported solves.
+pub fn export<W: Write>(
+    out: &mut W,
+    storage: &dyn Storage,
+    format: ExportFormat,
+    filter: &ExportFilter,
+) -> Result<usize, Error> {
+    let sizes = match filter.size {
+        Some(size) => vec![size],
+        None => storage.sizes()?,
+    };
+
+    if format == ExportFormat::Csv {
+        writeln!(
+            out,
+            "size,session,date,time,penalty,moves_cnt,moves,end_x,end_y"
+        )?;
+    }
+
+    let mut cnt = 0;
+    for size in sizes {
+        cnt += export_size(out, storage, size, format, filter)?;
+    }
+    Ok(cnt)
+}
+
+/// Exports solves of board with given size matching the filter, reading
+/// them from the storage by pages. Returns number of exported solves.
+fn export_size<W: Write>(
+    out: &mut W,
+    storage: &dyn Storage,
+    size: Vec2,
+    format: ExportFormat,
+    filter: &ExportFilter,
+) -> Result<usize, Error> {
+    let stats = storage.load(&size)?;
+    let mut cnt = 0;
+    for session in stats.sessions() {
+        let name = session.name();
+        // Pages start with the newest solve, so they're read from the end
+        let mut end = storage.count(&size, name)?;
+        while end > 0 {
+            let start = end.saturating_sub(PAGE_LEN);
+            let page: Vec<_> = storage
+                .page(&size, name, SortKey::Date, start, end - start)?
+                .into_iter()
+                .map(|(_, stat)| stat)
+                .collect();
+            cnt += export_solves(out, &page, size, name, format, filter)?;
+            end = start;
+        }
+    }
+    Ok(cnt)
+}
+
+/// Exports given solves of the session with given name matching the
+/// filter, from the oldest solve. Returns number of exported solves.
+fn export_solves<W: Write>(
+    out: &mut W,
+    solves: &[Stat],
+    size: Vec2,
+    session: &str,
+    format: ExportFormat,
+    filter: &ExportFilter,
+) -> Result<usize, Error> {
+    let mut cnt = 0;
+    let size = format!("{}x{}", size.x, size.y);
+    for stat in solves.iter().rev() {
+        if !filter.matches(stat) {
+            continue;
+        }
+
+        let end = stat.end();
+        let record = Record {
+            size: size.clone(),
+            session,
+            date: stat.date().to_rfc3339(),
+            time: stat.time().as_secs_f64(),
+            penalty: stat.penalty(),
+            moves_cnt: stat.metrics().mtm,
+            moves: stat.moves(),
+            end_x: end.x,
+            end_y: end.y,
+        };
+        match format {
+            ExportFormat::Csv => write_csv(out, &record)?,
+            ExportFormat::Jsonl => {
+                serde_json::to_writer(&mut *out, &record)?;
+                writeln!(out)?;
+            }
+        }
+        cnt += 1;
+    }
+    Ok(cnt)
+}
+
+/// Writes given record as CSV row
+fn write_csv<W: Write>(out: &mut W, record: &Record) -> Result<(), Error> {
+    let penalty = match record.penalty {
+        Penalty::None => "",
+        Penalty::PlusTwo => "+2",
+        Penalty::Dnf => "DNF",
+    };
+    writeln!(
+        out,
+        "{},{},{},{:.3},{},{},{},{},{}",
+        record.size,
+        csv_field(record.session),
+        record.date,
+        record.time,
+        penalty,
+        record.moves_cnt,
+        csv_field(record.moves),
+        record.end_x,
+        record.end_y,
+    )?;
+    Ok(())
+}
+
+/// Quotes given CSV field when it contains special characters
+fn csv_field(field: &str) -> String {
+    match field.contains([',', '"', '\n']) {
+        true => format!("\"{}\"", field.replace('"', "\"\"")),
+        false => field.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chrono::DateTime;
+
+    use crate::stats::{
+        import::Import, metrics::Metrics, stats_struct::Stats,
+    };
+
+    use super::*;
+
+    /// Creates stats with few solves of the 3x3 board, the oldest first
+    fn stats() -> Stats {
+        let solves = [
+            (12_345, "R d D", Vec2::new(1, 2), Penalty::None),
+            (9_870, "", Vec2::new(0, 0), Penalty::PlusTwo),
+            (15_002, "U > v L L", Vec2::new(0, 2), Penalty::Dnf),
+        ];
+
+        let mut stats = Stats::default();
+        for (i, (ms, moves, end, penalty)) in solves.into_iter().enumerate() {
+            let size = Vec2::new(3, 3);
+            let metrics = Metrics::new(moves, end, size).unwrap();
+            let date = DateTime::from_timestamp(1_700_000_000 + i as i64, 0);
+            let time = Duration::from_millis(ms);
+            let stat = Stat::new(time, metrics, moves.into(), end, 0)
+                .with_date(date.unwrap())
+                .with_penalty(penalty);
+            stats.add(stat);
+        }
+        stats
+    }
+
+    /// Exports given stats of the 3x3 board
+    fn export(stats: &Stats, format: ExportFormat) -> String {
+        let mut out = Vec::new();
+        let filter = ExportFilter::default();
+        let size = Vec2::new(3, 3);
+        export_solves(
+            &mut out,
+            stats.solves(),
+            size,
+            "default",
+            format,
+            &filter,
+        )
+        .unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn jsonl_round_trip() {
+        let stats = stats();
+        let text = export(&stats, ExportFormat::Jsonl);
+        let import = Import::parse(&text, Vec2::new(4, 4)).unwrap();
+
+        let [(size, solves)] = import.solves.as_slice() else {
+            panic!("solves of single size expected");
+        };
+        assert_eq!(*size, Vec2::new(3, 3));
+        let exported = stats.solves().iter().rev();
+        assert_eq!(solves.len(), exported.len());
+        for (solve, stat) in solves.iter().zip(exported) {
+            assert_eq!(solve.time(), stat.time());
+            assert_eq!(solve.date(), stat.date());
+            assert_eq!(solve.penalty(), stat.penalty());
+            assert_eq!(solve.moves(), stat.moves());
+            assert_eq!(solve.end(), stat.end());
+            assert_eq!(solve.metrics(), stat.metrics());
+        }
+
+        let mut merged = Stats::default();
+        assert_eq!(merged.merge(solves.clone()).len(), 3);
+        assert_eq!(export(&merged, ExportFormat::Jsonl), text);
+    }
+
+    #[test]
+    fn csv_rows() {
+        let text = export(&stats(), ExportFormat::Csv);
+        let rows: Vec<_> = text.lines().collect();
+        assert_eq!(
+            rows,
+            [
+                "3x3,default,2023-11-14T22:13:20+00:00,12.345,,2,R d D,1,2",
+                "3x3,default,2023-11-14T22:13:21+00:00,9.870,+2,0,,0,0",
+                "3x3,default,2023-11-14T22:13:22+00:00,15.002,DNF,3,\
+                U > v L L,0,2",
+            ]
+        );
+    }
+
+    #[test]
+    fn csv_fields_are_quoted() {
+        assert_eq!(csv_field("R d"), "R d");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn filters_dates() {
+        let filter = ExportFilter {
+            from: NaiveDate::from_ymd_opt(2023, 11, 15),
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        let size = Vec2::new(3, 3);
+        let stats = stats();
+        let format = ExportFormat::Csv;
+        let cnt =
+            export_solves(&mut out, stats.solves(), size, "", format, &filter);
+        assert_eq!(cnt.unwrap(), 0);
+        assert!(out.is_empty());
+    }
+}
@@ -0,0 +1,430 @@
+//! Importing solves exported from other timers.
+//!
+//! csTimer export is an object with `session<N>` keys, each containing list
+//! of solves `[[penalty, time], scramble, comment, timestamp]`. Penalty is
+//! `0`, `2000` (+2) or `-1` (DNF), time is in milliseconds and timestamp in
+//! seconds. csTimer doesn't store the board size, so the solves are imported
+//! to the given size.
+//!
+//! Web loopover export is a list of solves (or an object with `solves`
+//! list). Each solve is an object with `time` in milliseconds, `date` either
+//! in milliseconds since the epoch or in RFC 3339, board size given by
+//! `width` and `height` (or `size` as `WxH`), optional `moves` in the
+//! absolute notation or their count and optional `dnf` flag. Solves without
+//! the size are imported to the given size.
+//!
+//! JSON Lines export of loopover itself is imported as well, so the solves
+//! can be moved between machines. Each line is a solve with the fields
+//! written by [`super::export`].
+
+use std::{fmt::Display, time::Duration};
+
+use chrono::{DateTime, Utc};
+use serde_json::{Map, Value};
+use termint::geometry::Vec2;
+
+use crate::{
+    board::{
+        board_struct::{Board, MAX_SIZE},
+        notation,
+    },
+    error::Error,
+};
+
+use super::{
+    metrics::Metrics,
+    stat::{Penalty, Stat},
+};
+
+/// Format of the imported file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    CsTimer,
+    Loopover,
+    Jsonl,
+}
+
+impl ImportFormat {
+    /// Detects the format of the given parsed values of the file
+    fn detect(values: &[Value]) -> Option<Self> {
+        if !values.is_empty()
+            && values.iter().all(|v| v.get("moves_cnt").is_some())
+        {
+            return Some(Self::Jsonl);
+        }
+
+        match values {
+            [Value::Array(_)] => Some(Self::Loopover),
+            [Value::Object(map)] if map.contains_key("solves") => {
+                Some(Self::Loopover)
+            }
+            [Value::Object(map)]
+                if map.keys().any(|k| is_cstimer_session(k)) =>
+            {
+                Some(Self::CsTimer)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Display for ImportFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImportFormat::CsTimer => f.pad("csTimer"),
+            ImportFormat::Loopover => f.pad("web loopover"),
+            ImportFormat::Jsonl => f.pad("loopover JSON Lines"),
+        }
+    }
+}
+
+/// Solves parsed from the imported file grouped by the board size
+#[derive(Debug)]
+pub struct Import {
+    pub format: ImportFormat,
+    pub solves: Vec<(Vec2, Vec<Stat>)>,
+}
+
+impl Import {
+    /// Parses the exported file, solves without the board size are imported
+    /// to the given size
+    pub fn parse(text: &str, size: Vec2) -> Result<Self, Error> {
+        let values = serde_json::Deserializer::from_str(text)
+            .into_iter()
+            .collect::<Result<Vec<Value>, _>>()?;
+        let format = ImportFormat::detect(&values)
+            .ok_or("unknown format of the imported file")?;
+
+        let mut import = Self {
+            format,
+            solves: Vec::new(),
+        };
+        match format {
+            ImportFormat::CsTimer => import.parse_cstimer(&values[0], size)?,
+            ImportFormat::Loopover => {
+                import.parse_loopover(&values[0], size)?
+            }
+            ImportFormat::Jsonl => import.parse_jsonl(&values)?,
+        }
+        Ok(import)
+    }
+
+    /// Parses solves of all the csTimer sessions
+    fn parse_cstimer(
+        &mut self,
+        value: &Value,
+        size: Vec2,
+    ) -> Result<(), Error> {
+        let Value::Object(map) = value else {
+            return Ok(());
+        };
+
+        let sessions = map.iter().filter(|(k, _)| is_cstimer_session(k));
+        for (_, session) in sessions {
+            let Value::Array(solves) = session else {
+                continue;
+            };
+            for solve in solves {
+                let stat = parse_cstimer_solve(solve).ok_or_else(|| {
+                    Error::Msg(format!("invalid csTimer solve: {solve}"))
+                })?;
+                self.push(size, stat);
+            }
+        }
+        Ok(())
+    }
+
+    /// Parses solves of the web loopover export
+    fn parse_loopover(
+        &mut self,
+        value: &Value,
+        size: Vec2,
+    ) -> Result<(), Error> {
+        let solves = match value {
+            Value::Object(map) => map.get("solves"),
+            value => Some(value),
+        };
+        let Some(Value::Array(solves)) = solves else {
+            return Err(
+                "solves of the web loopover export must be list".into()
+            );
+        };
+
+        for solve in solves {
+            let Value::Object(solve) = solve else {
+                return Err(format!("invalid loopover solve: {solve}").into());
+            };
+            let (size, stat) = parse_loopover_solve(solve, size)?;
+            self.push(size, stat);
+        }
+        Ok(())
+    }
+
+    /// Parses solves of the loopover JSON Lines export
+    fn parse_jsonl(&mut self, values: &[Value]) -> Result<(), Error> {
+        for solve in values {
+            let Value::Object(solve) = solve else {
+                return Err(format!("invalid exported solve: {solve}").into());
+            };
+            let (size, stat) = parse_jsonl_solve(solve)?;
+            self.push(size, stat);
+        }
+        Ok(())
+    }
+
+    /// Adds stat to the solves of the given size
+    fn push(&mut self, size: Vec2, stat: Stat) {
+        match self.solves.iter_mut().find(|(s, _)| *s == size) {
+            Some((_, solves)) => solves.push(stat),
+            None => self.solves.push((size, vec![stat])),
+        }
+    }
+}
+
+/// Checks whether given key is csTimer session key
+fn is_cstimer_session(key: &str) -> bool {
+    key.strip_prefix("session").is_some_and(|n| {
+        !n.is_empty() && n.chars().all(|c| c.is_ascii_digit())
+    })
+}
+
+/// Parses single csTimer solve
+fn parse_cstimer_solve(solve: &Value) -> Option<Stat> {
+    let result = solve.get(0)?;
+    // csTimer stores the penalty in milliseconds, -1 is DNF
+    let penalty = match result.get(0)?.as_i64()? {
+        -1 => Penalty::Dnf,
+        0 => Penalty::None,
+        2000 => Penalty::PlusTwo,
+        _ => return None,
+    };
+    let time = Duration::from_millis(result.get(1)?.as_u64()?);
+    let date = DateTime::from_timestamp(solve.get(3)?.as_i64()?, 0)?;
+
+    let stat =
+        Stat::new(time, Metrics::default(), String::new(), Vec2::new(0, 0), 0);
+    Some(stat.with_date(date).with_penalty(penalty))
+}
+
+/// Parses single web loopover solve, returning it with its board size
+fn parse_loopover_solve(
+    solve: &Map<String, Value>,
+    size: Vec2,
+) -> Result<(Vec2, Stat), Error> {
+    let invalid = |field: &str| {
+        Error::Msg(format!("invalid or missing '{field}' of loopover solve"))
+    };
+
+    let time = solve
+        .get("time")
+        .and_then(|t| t.as_f64())
+        .and_then(|t| Duration::try_from_secs_f64(t / 1000.).ok())
+        .ok_or(invalid("time"))?;
+    let date = match solve.get("date").or(solve.get("timestamp")) {
+        Some(Value::Number(n)) => {
+            n.as_i64().and_then(DateTime::<Utc>::from_timestamp_millis)
+        }
+        Some(Value::String(s)) => {
+            DateTime::parse_from_rfc3339(s).ok().map(|d| d.to_utc())
+        }
+        _ => None,
+    }
+    .ok_or(invalid("date"))?;
+    let size = parse_loopover_size(solve)?.unwrap_or(size);
+
+    let (moves, end, metrics) = match solve.get("moves") {
+        Some(Value::String(moves)) => convert_moves(moves, size)?,
+        Some(Value::Number(n)) => {
+            let cnt = n.as_u64().ok_or(invalid("moves"))? as usize;
+            let metrics = Metrics {
+                stm: cnt,
+                mtm: cnt,
+                btm: cnt,
+                etm: cnt,
+                ..Default::default()
+            };
+            (String::new(), Vec2::new(0, 0), metrics)
+        }
+        Some(_) => return Err(invalid("moves")),
+        None => (String::new(), Vec2::new(0, 0), Metrics::default()),
+    };
+
+    let penalty = match solve.get("dnf").and_then(|d| d.as_bool()) {
+        Some(true) => Penalty::Dnf,
+        _ => Penalty::None,
+    };
+    let stat = Stat::new(time, metrics, moves, end, 0)
+        .with_date(date)
+        .with_penalty(penalty);
+    Ok((size, stat))
+}
+
+/// Parses single solve of the loopover JSON Lines export, returning it with
+/// its board size
+fn parse_jsonl_solve(
+    solve: &Map<String, Value>,
+) -> Result<(Vec2, Stat), Error> {
+    let invalid = |field: &str| {
+        Error::Msg(format!("invalid or missing '{field}' of exported solve"))
+    };
+    let usize_field = |field: &str| {
+        solve
+            .get(field)
+            .and_then(|v| v.as_u64())
+            .map(|v| v as usize)
+            .ok_or(invalid(field))
+    };
+
+    let size = parse_loopover_size(solve)?.ok_or(invalid("size"))?;
+    let time = solve
+        .get("time")
+        .and_then(|t| t.as_f64())
+        .and_then(|t| Duration::try_from_secs_f64(t).ok())
+        .ok_or(invalid("time"))?;
+    let date = solve
+        .get("date")
+        .and_then(|d| d.as_str())
+        .and_then(|d| DateTime::parse_from_rfc3339(d).ok())
+        .ok_or(invalid("date"))?
+        .to_utc();
+    let penalty = solve
+        .get("penalty")
+        .and_then(|p| serde_json::from_value(p.clone()).ok())
+        .ok_or(invalid("penalty"))?;
+    let moves = solve
+        .get("moves")
+        .and_then(|m| m.as_str())
+        .ok_or(invalid("moves"))?;
+    let end = Vec2::new(usize_field("end_x")?, usize_field("end_y")?);
+
+    let metrics = match moves.is_empty() {
+        true => Metrics {
+            mtm: usize_field("moves_cnt")?,
+            ..Default::default()
+        },
+        false => Metrics::new(moves, end, size)?,
+    };
+    let stat = Stat::new(time, metrics, moves.to_string(), end, 0)
+        .with_date(date)
+        .with_penalty(penalty);
+    Ok((size, stat))
+}
+
+/// Parses board size of the web loopover solve, [`None`] when not present
+fn parse_loopover_size(
+    solve: &Map<String, Value>,
+) -> Result<Option<Vec2>, Error> {
+    let size = match (solve.get("width"), solve.get("height")) {
+        (Some(w), Some(h)) => w.as_u64().zip(h.as_u64()),
+        _ => match solve.get("size") {
+            Some(Value::String(s)) => s
+                .split_once('x')
+                .and_then(|(w, h)| w.parse().ok().zip(h.parse().ok())),
+            Some(Value::Number(n)) => n.as_u64().map(|n| (n, n)),
+            Some(_) => None,
+            None => return Ok(None),
+        },
+    };
+
+    let valid = 2..=MAX_SIZE as u64;
+    match size {
+        Some((w, h)) if valid.contains(&w) && valid.contains(&h) => {
+            Ok(Some(Vec2::new(w as usize, h as usize)))
+        }
+        _ => Err("invalid size of loopover solve".into()),
+    }
+}
+
+/// Converts moves in the absolute notation to the cursor based moves used
+/// in the stats, returning them with the end cursor position and metrics
+fn convert_moves(
+    moves: &str,
+    size: Vec2,
+) -> Result<(String, Vec2, Metrics), Error> {
+    let moves = notation::to_cursor(moves, Vec2::new(0, 0), size)?;
+
+    let mut board = Board::new(size);
+    for c in moves.chars().filter(|c| !c.is_whitespace()) {
+        board.apply_move(c)?;
+    }
+    let metrics = Metrics::new(&moves, board.selected, size)?;
+    Ok((moves, board.selected, metrics))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::stats::stats_struct::Stats;
+
+    use super::*;
+
+    #[test]
+    fn cstimer_penalties() {
+        let text = r#"{"session1": [
+            [[0, 10500], "", "", 1700000000],
+            [[2000, 11000], "", "", 1700000100],
+            [[-1, 12000], "", "", 1700000200]
+        ]}"#;
+        let import = Import::parse(text, Vec2::new(3, 3)).unwrap();
+        assert_eq!(import.format, ImportFormat::CsTimer);
+
+        let (size, solves) = &import.solves[0];
+        assert_eq!(*size, Vec2::new(3, 3));
+        let penalties: Vec<_> = solves.iter().map(|s| s.penalty()).collect();
+        assert_eq!(penalties, [Penalty::None, Penalty::PlusTwo, Penalty::Dnf]);
+        assert_eq!(solves[1].time(), Duration::from_millis(11000));
+    }
+
+    #[test]
+    fn cstimer_unknown_penalty_is_rejected() {
+        let text = r#"{"session1": [[[500, 10500], "", "", 1700000000]]}"#;
+        assert!(Import::parse(text, Vec2::new(3, 3)).is_err());
+    }
+
+    #[test]
+    fn loopover_solve() {
+        let text = r#"[{"time": 5250, "date": 1700000000000,
+            "size": "4x4", "moves": 12, "dnf": true}]"#;
+        let import = Import::parse(text, Vec2::new(3, 3)).unwrap();
+        assert_eq!(import.format, ImportFormat::Loopover);
+
+        let (size, solves) = &import.solves[0];
+        assert_eq!(*size, Vec2::new(4, 4));
+        assert_eq!(solves[0].time(), Duration::from_millis(5250));
+        assert_eq!(solves[0].metrics().mtm, 12);
+        assert_eq!(solves[0].penalty(), Penalty::Dnf);
+    }
+
+    #[test]
+    fn loopover_size_is_capped() {
+        for size in ["1x4", "4x37", "37x37"] {
+            let text = format!(
+                r#"[{{"time": 5250, "date": 0,
+                "size": "{size}"}}]"#
+            );
+            assert!(Import::parse(&text, Vec2::new(3, 3)).is_err());
+        }
+    }
+
+    #[test]
+    fn same_file_is_imported_once() {
+        let text = r#"{"session1": [
+            [[0, 10500], "", "", 1700000000],
+            [[0, 11000], "", "", 1700000100]
+        ]}"#;
+        let mut stats = Stats::default();
+        for added in [2, 0] {
+            let mut import = Import::parse(text, Vec2::new(3, 3)).unwrap();
+            let (_, solves) = import.solves.remove(0);
+            assert_eq!(stats.merge(solves).len(), added);
+        }
+        assert_eq!(stats.solves().len(), 2);
+    }
+
+    #[test]
+    fn loopover_invalid_time() {
+        for time in ["-1", "1e300"] {
+            let text = format!(r#"[{{"time": {time}, "date": 0}}]"#);
+            assert!(Import::parse(&text, Vec2::new(3, 3)).is_err());
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use serde::{Deserialize, Serialize};
+use termint::geometry::Vec2;
+
+use crate::{board::board_struct::Board, error::Error};
+
+/// Move count metrics computed from the recorded moves
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize,
+)]
+pub struct Metrics {
+    /// Single tile metric, rotation of each line by one cell is a move
+    pub stm: usize,
+    /// Multi tile metric, rotation of all the selected lines is a move
+    pub mtm: usize,
+    /// Block turn metric, consecutive rotations of the same lines are
+    /// merged into single move
+    pub btm: usize,
+    /// Execution turn metric, each rotation, cursor move and selection
+    /// resize is a move
+    pub etm: usize,
+    /// Number of cursor moves
+    pub cursor: usize,
+}
+
+impl Metrics {
+    /// Computes metrics of the cursor based moves, which end with the
+    /// cursor on the given position, on board with given size
+    pub fn new(moves: &str, end: Vec2, size: Vec2) -> Result<Self, Error> {
+        let mut board = Board::new(size);
+        board.revert_moves(moves, end)?;
+
+        let mut counter = MetricsCounter::default();
+        for c in moves.chars().filter(|c| !c.is_whitespace()) {
+            board.apply_move(c)?;
+            counter.push(c, &board);
+        }
+        Ok(counter.metrics)
+    }
+}
+
+/// Counter of the metrics updated on each move of the solve
+#[derive(Debug, Default, Clone)]
+pub struct MetricsCounter {
+    metrics: Metrics,
+    /// Lines of the last rotation as (is column, first index, count) with
+    /// their total shift, so following rotations can be merged into them
+    block: Option<((bool, usize, usize), usize)>,
+}
+
+impl MetricsCounter {
+    /// Gets the metrics of the moves added so far
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    /// Adds given move, which was just applied to the given board. Rotation
+    /// doesn't change its own lines, so they are read after the move.
+    pub fn push(&mut self, c: char, board: &Board) {
+        let metrics = &mut self.metrics;
+        metrics.etm += 1;
+        let (lines, len, shift) = match c {
+            'U' | 'D' => {
+                let lines = (true, board.selected.x, board.span.x);
+                (lines, board.size.y, c == 'D')
+            }
+            'L' | 'R' => {
+                let lines = (false, board.selected.y, board.span.y);
+                (lines, board.size.x, c == 'R')
+            }
+            'u' | 'd' | 'l' | 'r' => {
+                metrics.cursor += 1;
+                return;
+            }
+            _ => return,
+        };
+
+        metrics.mtm += 1;
+        metrics.stm += lines.2;
+        let shift = if shift { 1 } else { len - 1 };
+        // Block turn is counted once its lines are shifted at all
+        let shift = match self.block {
+            Some((l, s)) if l == lines => {
+                metrics.btm -= (s != 0) as usize;
+                (s + shift) % len
+            }
+            _ => shift,
+        };
+        metrics.btm += (shift != 0) as usize;
+        self.block = Some((lines, shift));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_block_turns() {
+        let size = Vec2::new(3, 3);
+        // "D U" cancels out, so it isn't a block turn
+        let metrics = Metrics::new("R R d R L L D U", Vec2::new(1, 1), size);
+        let metrics = metrics.unwrap();
+        assert_eq!(metrics.mtm, 7);
+        assert_eq!(metrics.stm, 7);
+        assert_eq!(metrics.btm, 2);
+        assert_eq!(metrics.etm, 8);
+        assert_eq!(metrics.cursor, 1);
+    }
+
+    #[test]
+    fn incremental_matches_full() {
+        let size = Vec2::new(4, 3);
+        let mut board = Board::new(size);
+        let mut counter = MetricsCounter::default();
+        let moves = "R > R R v D d U D L r < R";
+        for c in moves.chars().filter(|c| !c.is_whitespace()) {
+            board.apply_move(c).unwrap();
+            counter.push(c, &board);
+        }
+
+        let full = Metrics::new(moves, board.selected, size).unwrap();
+        assert_eq!(*counter.metrics(), full);
+    }
+}
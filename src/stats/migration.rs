@@ -0,0 +1,141 @@
+//! Upgrading stats files saved by older versions of loopover.
+//!
+//! Stats files contain the `version` of their schema. Files without it are
+//! version 0. Each migration upgrades the parsed JSON by one version, so
+//! older files are upgraded by running all the following migrations.
+
+use chrono::Utc;
+use serde_json::{json, Map, Value};
+
+use crate::error::Error;
+
+/// Current version of the stats file schema
+pub const VERSION: u64 = 1;
+
+/// Migrations from each version to the next one, migration on index `i`
+/// upgrades version `i` to version `i + 1`
+const MIGRATIONS: [fn(&mut Map<String, Value>); VERSION as usize] = [sessions];
+
+/// Upgrades given stats file JSON to the current version
+pub fn migrate(value: &mut Value) -> Result<(), Error> {
+    let Value::Object(stats) = value else {
+        return Err("stats file must contain JSON object".into());
+    };
+
+    let version = match stats.get("version") {
+        Some(version) => version
+            .as_u64()
+            .ok_or("stats file version must be a number")?,
+        None => 0,
+    };
+    if version > VERSION {
+        return Err(format!(
+            "stats file version {version} is newer than supported version \
+            {VERSION}, update loopover to use it"
+        )
+        .into());
+    }
+
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(stats);
+    }
+    stats.insert("version".into(), VERSION.into());
+    Ok(())
+}
+
+/// Version 1 groups solves into sessions, so the solves are moved to the
+/// default session
+fn sessions(stats: &mut Map<String, Value>) {
+    if stats.contains_key("sessions") {
+        return;
+    }
+
+    let solves = stats.remove("solves").unwrap_or(json!([]));
+    let created = solves
+        .as_array()
+        .and_then(|s| s.last())
+        .and_then(|s| s.get("date"))
+        .cloned()
+        .unwrap_or(json!(Utc::now()));
+
+    stats.insert(
+        "sessions".into(),
+        json!([{ "name": "default", "created": created, "solves": solves }]),
+    );
+    stats.insert("active".into(), json!(0));
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+    use termint::geometry::Vec2;
+
+    use crate::stats::{average::Average, stat::Penalty, stats_struct::Stats};
+
+    use super::*;
+
+    const CURRENT: &str = include_str!("../../tests/fixtures/stats_v1.json");
+    const LEGACY: &str = include_str!("../../tests/fixtures/stats_v0.json");
+
+    #[test]
+    fn current_is_unchanged() {
+        let mut value: Value = serde_json::from_str(CURRENT).unwrap();
+        let expected = value.clone();
+        migrate(&mut value).unwrap();
+        assert_eq!(value, expected);
+    }
+
+    #[test]
+    fn current_loads() {
+        let stats = Stats::from_json(CURRENT, Vec2::new(3, 3)).unwrap();
+        assert_eq!(stats.sessions().len(), 2);
+        assert_eq!(stats.session().name(), "evening");
+        assert_eq!(stats.solves().len(), 5);
+        assert_eq!(stats.solves()[1].penalty(), Penalty::PlusTwo);
+        assert_eq!(stats.solves()[2].penalty(), Penalty::Dnf);
+        assert!(stats.average(Average::Ao5, 0).is_some());
+
+        let best = stats.best().as_ref().unwrap();
+        assert_eq!(best.moves(), "D r D");
+        assert_eq!(best.metrics().btm, 2);
+    }
+
+    #[test]
+    fn current_roundtrip() {
+        let stats = Stats::from_json(CURRENT, Vec2::new(3, 3)).unwrap();
+        let saved = serde_json::to_value(&stats).unwrap();
+        let expected: Value = serde_json::from_str(CURRENT).unwrap();
+        assert_eq!(saved, expected);
+    }
+
+    #[test]
+    fn legacy_migrates() {
+        let mut value: Value = serde_json::from_str(LEGACY).unwrap();
+        migrate(&mut value).unwrap();
+        assert_eq!(value["version"], VERSION);
+        assert!(value.get("solves").is_none());
+        assert_eq!(value["sessions"][0]["name"], "default");
+        assert_eq!(
+            value["sessions"][0]["solves"].as_array().unwrap().len(),
+            3
+        );
+        assert_eq!(value["sessions"][0]["created"], "2024-05-01T10:00:00Z");
+    }
+
+    #[test]
+    fn legacy_loads() {
+        let stats = Stats::from_json(LEGACY, Vec2::new(3, 3)).unwrap();
+        assert_eq!(stats.sessions().len(), 1);
+        assert_eq!(stats.solves().len(), 3);
+        // Metrics of the old solves are computed from the moves
+        assert_eq!(stats.solves()[0].metrics().mtm, 3);
+        assert_eq!(stats.solves()[0].penalty(), Penalty::None);
+    }
+
+    #[test]
+    fn newer_version_fails() {
+        let mut value: Value = serde_json::from_str(CURRENT).unwrap();
+        value["version"] = (VERSION + 1).into();
+        assert!(migrate(&mut value).is_err());
+    }
+}
@@ -1,2 +1,14 @@
+pub mod activity;
+pub mod average;
+pub mod distribution;
+pub mod export;
+pub mod import;
+pub mod metrics;
+pub mod migration;
+pub mod overview;
+pub mod records;
+pub mod session;
+pub mod sort;
 pub mod stat;
 pub mod stats_struct;
+pub mod storage;
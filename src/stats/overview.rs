@@ -0,0 +1,46 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use termint::geometry::Vec2;
+
+use crate::error::Error;
+
+use super::{average::Average, storage::Storage};
+
+/// Summary of the stats of board with one size
+#[derive(Debug, Clone)]
+pub struct SizeOverview {
+    pub size: Vec2,
+    /// Number of the solves across all the sessions
+    pub solves: usize,
+    /// All-time best single
+    pub best: Option<Duration>,
+    /// All-time best ao5
+    pub best_ao5: Option<Duration>,
+    /// Date of the newest solve
+    pub last: Option<DateTime<Utc>>,
+}
+
+impl SizeOverview {
+    /// Loads the summary of the stats of board with given size
+    pub fn load(storage: &dyn Storage, size: Vec2) -> Result<Self, Error> {
+        let dates = storage.dates(&size)?;
+        Ok(Self {
+            size,
+            solves: dates.len(),
+            best: storage.best(&size)?.and_then(|s| s.final_time()),
+            best_ao5: storage.best_average(&size, Average::Ao5)?,
+            last: dates.into_iter().max(),
+        })
+    }
+
+    /// Loads the summaries of all the sizes with saved stats, sorted by the
+    /// number of cells
+    pub fn load_all(storage: &dyn Storage) -> Result<Vec<Self>, Error> {
+        storage
+            .sizes()?
+            .into_iter()
+            .map(|size| Self::load(storage, size))
+            .collect()
+    }
+}
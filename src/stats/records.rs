@@ -0,0 +1,107 @@
+use std::{fmt::Display, time::Duration};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{average::Average, stat::format_duration};
+
+/// Kind of the personal best
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Record {
+    /// Fastest single solve
+    Single,
+    /// Solve with the fewest moves
+    Moves,
+    /// Best value of the average
+    Average(Average),
+}
+
+impl Record {
+    /// All the kinds of the personal bests
+    pub const ALL: [Record; Average::ALL.len() + 2] = [
+        Record::Single,
+        Record::Moves,
+        Record::Average(Average::Mo3),
+        Record::Average(Average::Ao5),
+        Record::Average(Average::Ao12),
+        Record::Average(Average::Ao50),
+        Record::Average(Average::Ao100),
+    ];
+}
+
+impl Display for Record {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Record::Single => f.pad("single"),
+            Record::Moves => f.pad("moves"),
+            Record::Average(avg) => f.pad(&avg.to_string()),
+        }
+    }
+}
+
+/// Value of the personal best, the lower value is better
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordValue {
+    Time(Duration),
+    Moves(usize),
+}
+
+impl Display for RecordValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecordValue::Time(time) => f.pad(&format_duration(*time)),
+            RecordValue::Moves(moves) => f.pad(&moves.to_string()),
+        }
+    }
+}
+
+/// Personal best set on the given date
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PersonalBest {
+    pub record: Record,
+    pub value: RecordValue,
+    /// The personal best this one replaced
+    pub previous: Option<RecordValue>,
+    pub date: DateTime<Utc>,
+}
+
+impl PersonalBest {
+    /// Gets the personal bests set on the given date, given the values of
+    /// [`Record::ALL`] before and after the solve
+    pub fn improved(
+        before: [Option<RecordValue>; Record::ALL.len()],
+        after: [Option<RecordValue>; Record::ALL.len()],
+        date: DateTime<Utc>,
+    ) -> Vec<PersonalBest> {
+        Record::ALL
+            .into_iter()
+            .zip(before.into_iter().zip(after))
+            .filter_map(|(record, (previous, value))| {
+                let value = value?;
+                previous.is_none_or(|p| value < p).then_some(PersonalBest {
+                    record,
+                    value,
+                    previous,
+                    date,
+                })
+            })
+            .collect()
+    }
+
+    /// Gets the improvement over the previous personal best
+    pub fn improvement(&self) -> Option<String> {
+        match (self.previous?, self.value) {
+            (RecordValue::Time(prev), RecordValue::Time(cur)) => {
+                Some(format!("-{:.3}", (prev - cur).as_secs_f64()))
+            }
+            (RecordValue::Moves(prev), RecordValue::Moves(cur)) => {
+                Some(format!("-{}", prev - cur))
+            }
+            _ => None,
+        }
+    }
+}
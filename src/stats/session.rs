@@ -0,0 +1,179 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    average::Average,
+    stat::{Penalty, Stat},
+};
+
+/// Change of the sessions of the stats
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionChange {
+    /// Creates new session with given name and makes it active
+    New(String),
+    /// Renames session with the first name to the second name
+    Rename(String, String),
+    /// Makes session with given name active
+    Switch(String),
+    /// Moves all solves of the first session to the second session and
+    /// removes the first session
+    Merge(String, String),
+}
+
+/// Named group of solves, so the stats can be tracked per practice session
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Session {
+    name: String,
+    created: DateTime<Utc>,
+    solves: Vec<Stat>,
+    /// Index of the best solve of the session
+    #[serde(skip)]
+    best: Option<usize>,
+    /// Best value of each of [`Average::ALL`] in the session
+    #[serde(skip)]
+    best_avgs: [Option<Duration>; Average::ALL.len()],
+}
+
+impl Session {
+    /// Creates new empty [`Session`] with given name
+    pub fn new<T: Into<String>>(name: T) -> Self {
+        Self {
+            name: name.into(),
+            created: Utc::now(),
+            solves: Vec::new(),
+            best: None,
+            best_avgs: Default::default(),
+        }
+    }
+
+    /// Creates new [`Session`] with given name containing given solves
+    pub fn with_solves<T: Into<String>>(name: T, solves: Vec<Stat>) -> Self {
+        let mut session = Self::new(name);
+        if let Some(first) = solves.last() {
+            session.created = first.date();
+        }
+        session.solves = solves;
+        session.recompute();
+        session
+    }
+
+    /// Sets the date when the session was created
+    #[cfg(feature = "sqlite")]
+    pub fn with_created(mut self, created: DateTime<Utc>) -> Self {
+        self.created = created;
+        self
+    }
+
+    /// Gets the name of the session
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Sets the name of the session
+    pub fn set_name<T: Into<String>>(&mut self, name: T) {
+        self.name = name.into();
+    }
+
+    /// Gets the date when the session was created
+    #[cfg(feature = "sqlite")]
+    pub fn created(&self) -> DateTime<Utc> {
+        self.created
+    }
+
+    /// Gets the solves of the session, the newest solve is first
+    pub fn solves(&self) -> &Vec<Stat> {
+        &self.solves
+    }
+
+    /// Gets mutable solves of the session, [`Session::recompute`] has to be
+    /// called after changing them
+    pub fn solves_mut(&mut self) -> &mut Vec<Stat> {
+        &mut self.solves
+    }
+
+    /// Gets the best solve of the session
+    pub fn best(&self) -> Option<&Stat> {
+        self.solves.get(self.best?)
+    }
+
+    /// Adds given stat as the newest solve of the session
+    pub fn add(&mut self, stat: Stat) {
+        self.best = match stat.is_better(self.best()) {
+            true => Some(0),
+            false => self.best.map(|b| b + 1),
+        };
+        self.solves.insert(0, stat);
+
+        for (id, avg) in Average::ALL.iter().enumerate() {
+            let Some(Some(cur)) = self.average(*avg, 0) else {
+                continue;
+            };
+            if self.best_avgs[id].is_none_or(|b| cur < b) {
+                self.best_avgs[id] = Some(cur);
+            }
+        }
+    }
+
+    /// Sets penalty of the solve on the given index
+    pub fn set_penalty(&mut self, id: usize, penalty: Penalty) {
+        if let Some(stat) = self.solves.get_mut(id) {
+            stat.set_penalty(penalty);
+            self.recompute();
+        }
+    }
+
+    /// Removes the solve on the given index
+    pub fn remove(&mut self, id: usize) -> Option<Stat> {
+        if id >= self.solves.len() {
+            return None;
+        }
+        let stat = self.solves.remove(id);
+        self.recompute();
+        Some(stat)
+    }
+
+    /// Moves all the solves from the given session to this session, keeping
+    /// the solves sorted from the newest
+    pub fn merge(&mut self, other: Session) {
+        self.solves.extend(other.solves);
+        self.solves.sort_by_key(|s| std::cmp::Reverse(s.date()));
+        self.created = self.created.min(other.created);
+        self.recompute();
+    }
+
+    /// Gets the average of the solves starting with the solve on the given
+    /// index, [`None`] when there are not enough solves and `Some(None)` when
+    /// the average is DNF
+    pub fn average(
+        &self,
+        avg: Average,
+        offset: usize,
+    ) -> Option<Option<Duration>> {
+        avg.compute(self.solves.iter().skip(offset).map(|s| s.final_time()))
+    }
+
+    /// Gets the best value of the given average
+    pub fn best_average(&self, avg: Average) -> Option<Duration> {
+        let id = Average::ALL.iter().position(|a| *a == avg)?;
+        self.best_avgs[id]
+    }
+
+    /// Recomputes the best solve and the best value of each average
+    pub fn recompute(&mut self) {
+        self.best = None;
+        for (id, stat) in self.solves.iter().enumerate().rev() {
+            if stat.is_better(self.best()) {
+                self.best = Some(id);
+            }
+        }
+
+        for (id, avg) in Average::ALL.iter().enumerate() {
+            self.best_avgs[id] = (0..self.solves.len())
+                .map_while(|i| self.average(*avg, i))
+                .flatten()
+                .min();
+        }
+    }
+}
@@ -0,0 +1,63 @@
+//! Sorting of the session solves, so the stats table can be paged in any
+//! order.
+
+use std::fmt::Display;
+
+use super::stat::Stat;
+
+/// Key the solves are sorted by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortKey {
+    /// Newest solve first
+    #[default]
+    Date,
+    /// Fastest solve first, DNF solves last
+    Time,
+    /// Solve with the fewest moves first, solves without moves last
+    Moves,
+    /// Solve with the highest moves per second first
+    Mps,
+}
+
+impl SortKey {
+    /// Gets the next sort key, the last one is followed by the first one
+    pub fn next(self) -> Self {
+        match self {
+            SortKey::Date => SortKey::Time,
+            SortKey::Time => SortKey::Moves,
+            SortKey::Moves => SortKey::Mps,
+            SortKey::Mps => SortKey::Date,
+        }
+    }
+
+    /// Gets indices of given solves, which are the newest first, sorted by
+    /// the key. Solves with equal keys stay the newest first.
+    pub fn order(self, solves: &[Stat]) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..solves.len()).collect();
+        match self {
+            SortKey::Date => {}
+            SortKey::Time => order.sort_by_key(|id| {
+                let time = solves[*id].final_time();
+                (time.is_none(), time)
+            }),
+            SortKey::Moves => order.sort_by_key(|id| {
+                let stat = &solves[*id];
+                (!stat.has_moves(), stat.metrics().mtm)
+            }),
+            SortKey::Mps => order
+                .sort_by(|a, b| solves[*b].mps().total_cmp(&solves[*a].mps())),
+        }
+        order
+    }
+}
+
+impl Display for SortKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SortKey::Date => write!(f, "date"),
+            SortKey::Time => write!(f, "time"),
+            SortKey::Moves => write!(f, "moves"),
+            SortKey::Mps => write!(f, "mps"),
+        }
+    }
+}
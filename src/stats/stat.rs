@@ -4,6 +4,22 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use termint::geometry::Vec2;
 
+use super::metrics::Metrics;
+
+/// Penalty of the solve
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Penalty {
+    #[default]
+    None,
+    /// Two seconds are added to the time
+    PlusTwo,
+    /// Did not finish, solve doesn't have any time
+    Dnf,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Stat {
     time: Duration,
@@ -12,34 +28,148 @@ pub struct Stat {
     moves: String,
     end_x: usize,
     end_y: usize,
+    #[serde(default)]
+    hints: usize,
+    #[serde(default)]
+    seed: Option<u64>,
+    #[serde(default)]
+    scramble: Option<String>,
+    #[serde(default)]
+    undos: usize,
+    /// Offset of each move from the start of the solve in milliseconds
+    #[serde(default)]
+    times: Vec<u64>,
+    #[serde(default)]
+    metrics: Option<Metrics>,
+    #[serde(default)]
+    penalty: Penalty,
 }
 
 impl Stat {
     /// Creates new [`Stat`]
     pub fn new(
         time: Duration,
-        moves_cnt: usize,
+        metrics: Metrics,
         moves: String,
         end: Vec2,
+        hints: usize,
     ) -> Self {
         Self {
             time,
             date: Utc::now(),
-            moves_cnt,
+            moves_cnt: metrics.mtm,
             moves,
             end_x: end.x,
             end_y: end.y,
+            hints,
+            seed: None,
+            scramble: None,
+            undos: 0,
+            times: Vec::new(),
+            metrics: Some(metrics),
+            penalty: Penalty::None,
         }
     }
 
+    /// Sets the seed and the scramble string of the [`Stat`]
+    pub fn with_scramble(
+        mut self,
+        seed: Option<u64>,
+        scramble: String,
+    ) -> Self {
+        self.seed = seed;
+        self.scramble = Some(scramble);
+        self
+    }
+
+    /// Sets number of undos used during the solve
+    pub fn with_undos(mut self, undos: usize) -> Self {
+        self.undos = undos;
+        self
+    }
+
+    /// Sets the date of the solve, used when importing older solves
+    pub fn with_date(mut self, date: DateTime<Utc>) -> Self {
+        self.date = date;
+        self
+    }
+
+    /// Sets the penalty of the solve
+    pub fn with_penalty(mut self, penalty: Penalty) -> Self {
+        self.penalty = penalty;
+        self
+    }
+
+    /// Sets the offsets of the moves from the start of the solve
+    pub fn with_times(mut self, times: Vec<u64>) -> Self {
+        self.times = times;
+        self
+    }
+
     /// Gets the time of the [`Stat`]
     pub fn time(&self) -> Duration {
         self.time
     }
 
-    /// Gets the moves count
-    pub fn moves_cnt(&self) -> usize {
-        self.moves_cnt
+    /// Gets the time including the penalty, [`None`] when the solve is DNF
+    pub fn final_time(&self) -> Option<Duration> {
+        match self.penalty {
+            Penalty::None => Some(self.time),
+            Penalty::PlusTwo => Some(self.time + Duration::from_secs(2)),
+            Penalty::Dnf => None,
+        }
+    }
+
+    /// Checks whether the solve is better than the given best solve. Solves
+    /// using hints and DNF solves are never the best.
+    pub fn is_better(&self, best: Option<&Stat>) -> bool {
+        let Some(time) = self.final_time() else {
+            return false;
+        };
+        if self.hints > 0 {
+            return false;
+        }
+        best.and_then(|b| b.final_time()).is_none_or(|b| time < b)
+    }
+
+    /// Gets the penalty of the solve
+    pub fn penalty(&self) -> Penalty {
+        self.penalty
+    }
+
+    /// Sets the penalty of the solve
+    pub fn set_penalty(&mut self, penalty: Penalty) {
+        self.penalty = penalty;
+    }
+
+    /// Gets the move count metrics of the solve
+    pub fn metrics(&self) -> Metrics {
+        self.metrics.unwrap_or(Metrics {
+            mtm: self.moves_cnt,
+            ..Default::default()
+        })
+    }
+
+    /// Checks whether the solve has any recorded moves, imported solves
+    /// might be saved without them
+    pub fn has_moves(&self) -> bool {
+        self.metrics().mtm > 0
+    }
+
+    /// Gets the moves per second of the solve, the penalty isn't included
+    pub fn mps(&self) -> f64 {
+        match self.time.as_secs_f64() {
+            0. => 0.,
+            secs => self.metrics().mtm as f64 / secs,
+        }
+    }
+
+    /// Computes the metrics of the solve on board with given size, when
+    /// they are missing
+    pub fn fill_metrics(&mut self, size: Vec2) {
+        if self.metrics.is_none() {
+            self.metrics = Metrics::new(&self.moves, self.end(), size).ok();
+        }
     }
 
     /// Gets the moves used to solve the scramble
@@ -47,26 +177,81 @@ impl Stat {
         &self.moves
     }
 
+    /// Gets the offset of the move with given index from the start of the
+    /// solve. Solves recorded without the offsets have the moves spread
+    /// evenly across the solve time.
+    pub fn move_time(&self, id: usize) -> Duration {
+        if let Some(ms) = self.times.get(id) {
+            return Duration::from_millis(*ms);
+        }
+
+        let cnt = self.moves.chars().filter(|c| !c.is_whitespace()).count();
+        match cnt {
+            0 => Duration::from_secs(0),
+            cnt => self.time.mul_f64(id as f64 / cnt as f64),
+        }
+    }
+
     /// Gets the end position of the cursor
     pub fn end(&self) -> Vec2 {
         Vec2::new(self.end_x, self.end_y)
     }
 
+    /// Gets number of hints used during the solve
+    pub fn hints(&self) -> usize {
+        self.hints
+    }
+
+    /// Gets number of undos used during the solve
+    pub fn undos(&self) -> usize {
+        self.undos
+    }
+
+    /// Gets the seed the board was scrambled with
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    /// Gets the scramble string of the board state before the solve
+    pub fn scramble(&self) -> Option<&String> {
+        self.scramble.as_ref()
+    }
+
     /// Gets the date of the solve
     pub fn date(&self) -> DateTime<Utc> {
         self.date
     }
 
+    /// Formats the time including the penalty
     pub fn format_time(&self) -> String {
-        let total = self.time.as_millis();
-        let mins = total / 60000;
-        let secs = (total / 1000) % 60;
-        let millis = total % 1000;
-
-        if mins > 0 {
-            format!("{}:{:02}.{:03}", mins, secs, millis)
-        } else {
-            format!("{:02}.{:03}", secs, millis)
+        match self.penalty {
+            Penalty::None => format_duration(self.time),
+            Penalty::PlusTwo => {
+                format!(
+                    "{}+",
+                    format_duration(self.time + Duration::from_secs(2))
+                )
+            }
+            Penalty::Dnf => "DNF".to_string(),
         }
     }
 }
+
+/// Formats given time, where [`None`] is DNF
+pub fn format_result(time: Option<Duration>) -> String {
+    time.map(format_duration).unwrap_or("DNF".to_string())
+}
+
+/// Formats given duration as the solve time
+pub fn format_duration(time: Duration) -> String {
+    let total = time.as_millis();
+    let mins = total / 60000;
+    let secs = (total / 1000) % 60;
+    let millis = total % 1000;
+
+    if mins > 0 {
+        format!("{}:{:02}.{:03}", mins, secs, millis)
+    } else {
+        format!("{:02}.{:03}", secs, millis)
+    }
+}
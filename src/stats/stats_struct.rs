@@ -1,87 +1,674 @@
-use std::{
-    fs::{create_dir_all, write, File},
-    ops::Index,
-    path::PathBuf,
-};
+use std::{collections::HashSet, ops::Index, path::PathBuf, time::Duration};
 
+use chrono::{DateTime, Utc};
 use dirs::config_dir;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use termint::geometry::Vec2;
 
 use crate::error::Error;
 
-use super::stat::Stat;
+use super::{
+    average::Average,
+    migration,
+    records::{PersonalBest, Record, RecordValue},
+    session::{Session, SessionChange},
+    stat::{Penalty, Stat},
+};
+
+/// Name of the session the solves are in when no session was created
+const DEFAULT_SESSION: &str = "default";
 
-#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Stats {
-    solves: Vec<Stat>,
+    /// Version of the stats file schema
+    version: u64,
+    /// All-time best solve
     best: Option<Stat>,
+    #[serde(default)]
+    sessions: Vec<Session>,
+    /// Index of the active session
+    #[serde(default)]
+    active: usize,
+    /// Progression of all the personal bests, from the oldest. It's kept
+    /// when the solves are edited or deleted.
+    #[serde(default)]
+    records: Vec<PersonalBest>,
 }
 
 impl Stats {
-    /// Loads the stats with given board size
-    pub fn load(size: &Vec2) -> Self {
-        match std::fs::read_to_string(Stats::get_file(size)) {
-            Ok(s) => serde_json::from_str::<Self>(&s).unwrap_or_default(),
-            Err(_) => Stats::default(),
+    /// Parses the stats of board with given size from JSON, upgrading it
+    /// from the older versions
+    pub fn from_json(text: &str, size: Vec2) -> Result<Self, Error> {
+        let mut value: Value = serde_json::from_str(text)?;
+        migration::migrate(&mut value)?;
+        let has_records = value.get("records").is_some();
+        let mut stats: Self = serde_json::from_value(value)?;
+
+        if stats.sessions.is_empty() {
+            stats.sessions.push(Session::new(DEFAULT_SESSION));
+        }
+        stats.active = stats.active.min(stats.sessions.len() - 1);
+
+        for session in stats.sessions.iter_mut() {
+            for stat in session.solves_mut() {
+                stat.fill_metrics(size);
+            }
+        }
+        if let Some(best) = stats.best.as_mut() {
+            best.fill_metrics(size);
+        }
+        stats.recompute();
+        // Files saved before the history was kept only have the solves
+        if !has_records {
+            stats.rebuild_records();
         }
+        Ok(stats)
     }
 
-    /// Saves stats wit
-    pub fn save(&self, size: &Vec2) -> Result<(), Error> {
-        let mut path = Stats::get_dir()?;
-        create_dir_all(&path)?;
+    /// Serializes the stats to JSON
+    pub fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string(self)?)
+    }
 
-        path.push(format!("{}x{}.json", size.x, size.y));
-        File::create(&path)?;
+    /// Creates new [`Stats`] from the given sessions, where the session on
+    /// the given index is active, the personal bests history and the
+    /// all-time best solve. The sessions don't need to contain all their
+    /// solves, so the all-time values aren't computed from them.
+    #[cfg(feature = "sqlite")]
+    pub fn from_sessions(
+        sessions: Vec<Session>,
+        active: usize,
+        records: Vec<PersonalBest>,
+        best: Option<Stat>,
+    ) -> Self {
+        let mut stats = Self {
+            best,
+            sessions,
+            active,
+            records,
+            ..Default::default()
+        };
+        if stats.sessions.is_empty() {
+            stats.sessions.push(Session::new(DEFAULT_SESSION));
+        }
+        stats.active = stats.active.min(stats.sessions.len() - 1);
+        stats
+    }
 
-        let text = serde_json::to_string::<Self>(self)?;
-        write(path, text)?;
-        Ok(())
+    /// Sets the all-time best solve, when the solves are loaded only
+    /// partially
+    #[cfg(feature = "sqlite")]
+    pub fn set_best(&mut self, best: Option<Stat>) {
+        self.best = best;
     }
 
-    /// Gets the solves
+    /// Gets the solves of the active session
     pub fn solves(&self) -> &Vec<Stat> {
-        &self.solves
+        self.session().solves()
     }
 
-    /// Gets best solve of the stats
+    /// Gets all-time best solve of the stats
     pub fn best(&self) -> &Option<Stat> {
         &self.best
     }
 
-    /// Adds given stat to the stats
-    pub fn add(&mut self, stat: Stat) {
-        match &self.best {
-            Some(b) if stat.time() < b.time() => {
-                self.best = Some(stat.clone())
+    /// Gets the active session
+    pub fn session(&self) -> &Session {
+        &self.sessions[self.active]
+    }
+
+    /// Gets all the sessions
+    pub fn sessions(&self) -> &[Session] {
+        &self.sessions
+    }
+
+    /// Adds given stat to the active session. Solves using hints are not
+    /// considered for the best solve. The personal bests set by the solve
+    /// are added to the history and the ones improving the previous
+    /// personal best are returned.
+    pub fn add(&mut self, stat: Stat) -> Vec<PersonalBest> {
+        let date = stat.date();
+        let before = Record::ALL.map(|r| self.record(r));
+        self.push(stat);
+        let after = Record::ALL.map(|r| self.record(r));
+        let pbs = PersonalBest::improved(before, after, date);
+        self.add_records(pbs)
+    }
+
+    /// Adds given stat to the active session without updating the personal
+    /// bests history
+    pub fn push(&mut self, stat: Stat) {
+        if stat.is_better(self.best.as_ref()) {
+            self.best = Some(stat.clone());
+        }
+        self.sessions[self.active].add(stat);
+    }
+
+    /// Adds given personal bests to the history, which is kept sorted by
+    /// the date. Returns the ones improving the previous personal best.
+    pub fn add_records(
+        &mut self,
+        records: Vec<PersonalBest>,
+    ) -> Vec<PersonalBest> {
+        self.records.extend(records.iter().copied());
+        self.records.sort_by_key(|pb| pb.date);
+        records
+            .into_iter()
+            .filter(|pb| pb.previous.is_some())
+            .collect()
+    }
+
+    /// Gets the all-time personal best of given record
+    pub fn record(&self, record: Record) -> Option<RecordValue> {
+        match record {
+            Record::Single => self
+                .best
+                .as_ref()
+                .and_then(|b| b.final_time())
+                .map(RecordValue::Time),
+            Record::Moves => self
+                .fewest_moves()
+                .map(|s| RecordValue::Moves(s.metrics().mtm)),
+            Record::Average(avg) => {
+                self.all_time_average(avg).map(RecordValue::Time)
+            }
+        }
+    }
+
+    /// Gets the all-time solve with the fewest moves. Solves using hints,
+    /// DNF solves and solves without recorded moves are skipped.
+    pub fn fewest_moves(&self) -> Option<&Stat> {
+        self.eligible_solves()
+            .filter(|s| s.has_moves())
+            .min_by_key(|s| s.metrics().mtm)
+    }
+
+    /// Gets the all-time solve with the highest moves per second. Solves
+    /// using hints, DNF solves and solves without recorded moves are
+    /// skipped.
+    pub fn highest_mps(&self) -> Option<&Stat> {
+        self.eligible_solves()
+            .filter(|s| s.has_moves())
+            .max_by(|a, b| a.mps().total_cmp(&b.mps()))
+    }
+
+    /// Gets the progression of all the personal bests, from the oldest
+    pub fn pb_history(&self) -> &[PersonalBest] {
+        &self.records
+    }
+
+    /// Rebuilds the personal bests history from the solves. Each personal
+    /// best is set by a solve or by the last solve of the average.
+    pub fn rebuild_records(&mut self) {
+        let mut values = Vec::new();
+        for session in self.sessions.iter() {
+            for (id, stat) in session.solves().iter().enumerate() {
+                let date = stat.date();
+                if let Some(time) =
+                    stat.final_time().filter(|_| stat.is_better(None))
+                {
+                    values.push((
+                        date,
+                        Record::Single,
+                        RecordValue::Time(time),
+                    ));
+                    if stat.has_moves() {
+                        let moves = RecordValue::Moves(stat.metrics().mtm);
+                        values.push((date, Record::Moves, moves));
+                    }
+                }
+                for avg in Average::ALL {
+                    if let Some(Some(time)) = session.average(avg, id) {
+                        let record = Record::Average(avg);
+                        values.push((date, record, RecordValue::Time(time)));
+                    }
+                }
+            }
+        }
+        values.sort_by_key(|(date, _, _)| *date);
+
+        let mut bests = [None; Record::ALL.len()];
+        self.records.clear();
+        for (date, record, value) in values {
+            let Some(id) = Record::ALL.iter().position(|r| *r == record)
+            else {
+                continue;
+            };
+            if bests[id].is_some_and(|best| value >= best) {
+                continue;
+            }
+            self.records.push(PersonalBest {
+                record,
+                value,
+                previous: bests[id],
+                date,
+            });
+            bests[id] = Some(value);
+        }
+    }
+
+    /// Adds given solves to the active session, skipping solves with the
+    /// same date as any other solve. Personal bests history is built from
+    /// the solves when it's empty, otherwise the personal bests improved by
+    /// the solves are added to it. Returns the added solves.
+    pub fn merge(&mut self, solves: Vec<Stat>) -> Vec<Stat> {
+        let had_records = !self.records.is_empty();
+        let before = Record::ALL.map(|r| self.record(r));
+        let added = self.extend(solves);
+        let Some(date) = added.iter().map(|s| s.date()).max() else {
+            return added;
+        };
+
+        if had_records {
+            let after = Record::ALL.map(|r| self.record(r));
+            self.add_records(PersonalBest::improved(before, after, date));
+        } else {
+            self.rebuild_records();
+        }
+        added
+    }
+
+    /// Adds given solves to the active session without updating the
+    /// personal bests history, skipping solves with the same date as any
+    /// other solve. Returns the added solves.
+    pub fn extend(&mut self, solves: Vec<Stat>) -> Vec<Stat> {
+        let mut dates: HashSet<i64> = self
+            .sessions
+            .iter()
+            .flat_map(|s| s.solves())
+            .map(|s| s.date().timestamp_millis())
+            .collect();
+        let added: Vec<Stat> = solves
+            .into_iter()
+            .filter(|s| dates.insert(s.date().timestamp_millis()))
+            .collect();
+
+        if !added.is_empty() {
+            let session = Session::with_solves("", added.clone());
+            self.sessions[self.active].merge(session);
+            self.recompute_best();
+        }
+        added
+    }
+
+    /// Gets index of the solve with given date in the active session
+    pub fn position(&self, date: DateTime<Utc>) -> Option<usize> {
+        self.solves().iter().position(|s| s.date() == date)
+    }
+
+    /// Sets penalty of the solve on the given index in the active session
+    /// and recomputes the best solve and averages
+    pub fn set_penalty(&mut self, id: usize, penalty: Penalty) {
+        self.sessions[self.active].set_penalty(id, penalty);
+        self.recompute_best();
+    }
+
+    /// Removes the solve on the given index in the active session and
+    /// recomputes the best solve and averages
+    pub fn remove(&mut self, id: usize) -> Option<Stat> {
+        let stat = self.sessions[self.active].remove(id)?;
+        self.recompute_best();
+        Some(stat)
+    }
+
+    /// Recomputes the best solve and the best averages from all the solves
+    pub fn recompute(&mut self) {
+        for session in self.sessions.iter_mut() {
+            session.recompute();
+        }
+        self.recompute_best();
+    }
+
+    /// Gets the average of the active session solves starting with the solve
+    /// on the given index, [`None`] when there are not enough solves and
+    /// `Some(None)` when the average is DNF
+    pub fn average(
+        &self,
+        avg: Average,
+        offset: usize,
+    ) -> Option<Option<Duration>> {
+        self.session().average(avg, offset)
+    }
+
+    /// Gets the best value of the given average in the active session
+    pub fn best_average(&self, avg: Average) -> Option<Duration> {
+        self.session().best_average(avg)
+    }
+
+    /// Gets the all-time best value of the given average. Averages don't
+    /// span across multiple sessions.
+    pub fn all_time_average(&self, avg: Average) -> Option<Duration> {
+        self.sessions
+            .iter()
+            .filter_map(|s| s.best_average(avg))
+            .min()
+    }
+
+    /// Creates new session with given name and makes it active
+    pub fn new_session(&mut self, name: &str) -> Result<(), Error> {
+        self.check_name(name)?;
+        self.sessions.push(Session::new(name));
+        self.active = self.sessions.len() - 1;
+        Ok(())
+    }
+
+    /// Generates name of the new session, which isn't used by any session
+    pub fn next_session_name(&self) -> String {
+        (self.sessions.len() + 1..)
+            .map(|i| format!("session {i}"))
+            .find(|n| self.find_session(n).is_err())
+            .unwrap_or_default()
+    }
+
+    /// Renames session with given name
+    pub fn rename_session(
+        &mut self,
+        old: &str,
+        new: &str,
+    ) -> Result<(), Error> {
+        let id = self.find_session(old)?;
+        if old != new {
+            self.check_name(new)?;
+        }
+        self.sessions[id].set_name(new);
+        Ok(())
+    }
+
+    /// Makes session with given name active
+    pub fn switch_session(&mut self, name: &str) -> Result<(), Error> {
+        self.active = self.find_session(name)?;
+        Ok(())
+    }
+
+    /// Gets name of the session given number of sessions after the active
+    /// session, cycling from the last session to the first one
+    pub fn cycled_session(&self, step: isize) -> &str {
+        let len = self.sessions.len() as isize;
+        let id = (self.active as isize + step).rem_euclid(len) as usize;
+        self.sessions[id].name()
+    }
+
+    /// Moves all solves from session `from` to session `into` and removes
+    /// the session `from`
+    pub fn merge_sessions(
+        &mut self,
+        from: &str,
+        into: &str,
+    ) -> Result<(), Error> {
+        let from_id = self.find_session(from)?;
+        let into_id = self.find_session(into)?;
+        if from_id == into_id {
+            return Err("can't merge session into itself".into());
+        }
+
+        let active = self.session().name().to_string();
+        let session = self.sessions.remove(from_id);
+        let into_id = self.find_session(into)?;
+        self.sessions[into_id].merge(session);
+        self.active = self.find_session(&active).unwrap_or(into_id);
+        Ok(())
+    }
+
+    /// Applies given change of the sessions
+    pub fn change_session(
+        &mut self,
+        change: &SessionChange,
+    ) -> Result<(), Error> {
+        match change {
+            SessionChange::New(name) => self.new_session(name),
+            SessionChange::Rename(old, new) => self.rename_session(old, new),
+            SessionChange::Switch(name) => self.switch_session(name),
+            SessionChange::Merge(from, into) => {
+                self.merge_sessions(from, into)
             }
-            None => self.best = Some(stat.clone()),
-            _ => {}
         }
-        self.solves.insert(0, stat);
+    }
+
+    /// Makes session with given name active, when it exists
+    pub fn activate(&mut self, name: &str) {
+        if let Ok(id) = self.find_session(name) {
+            self.active = id;
+        }
+    }
+
+    /// Gets solves of all the sessions that can be personal bests
+    fn eligible_solves(&self) -> impl Iterator<Item = &Stat> {
+        self.sessions
+            .iter()
+            .flat_map(|s| s.solves())
+            .filter(|s| s.is_better(None))
+    }
+
+    /// Recomputes the all-time best solve
+    fn recompute_best(&mut self) {
+        let mut best: Option<&Stat> = None;
+        for stat in self.sessions.iter().filter_map(|s| s.best()) {
+            if stat.is_better(best) {
+                best = Some(stat);
+            }
+        }
+        self.best = best.cloned();
+    }
+
+    /// Gets index of the session with given name
+    fn find_session(&self, name: &str) -> Result<usize, Error> {
+        self.sessions
+            .iter()
+            .position(|s| s.name() == name)
+            .ok_or(Error::Msg(format!("session '{name}' doesn't exist")))
+    }
+
+    /// Checks whether given name can be used as a new session name
+    fn check_name(&self, name: &str) -> Result<(), Error> {
+        if name.trim().is_empty() {
+            return Err("session name can't be empty".into());
+        }
+        match self.find_session(name) {
+            Ok(_) => Err(format!("session '{name}' already exists").into()),
+            Err(_) => Ok(()),
+        }
     }
 
     /// Gets stats directory
-    fn get_dir() -> Result<PathBuf, Error> {
+    pub fn get_dir() -> Result<PathBuf, Error> {
         let mut config = config_dir()
             .ok_or(Error::Msg("Can't get stats directory".to_string()))?;
         config.push("loopover/stats");
         Ok(config)
     }
-
-    /// Gets stats file
-    fn get_file(size: &Vec2) -> PathBuf {
-        Stats::get_dir()
-            .unwrap_or(PathBuf::from("."))
-            .join(format!("{}x{}.json", size.x, size.y))
-    }
 }
 
 impl Index<usize> for Stats {
     type Output = Stat;
 
     fn index(&self, index: usize) -> &Self::Output {
-        &self.solves[index]
+        &self.session().solves()[index]
+    }
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Self {
+            version: migration::VERSION,
+            best: None,
+            sessions: vec![Session::new(DEFAULT_SESSION)],
+            active: 0,
+            records: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        stats::sort::SortKey,
+        test_util::{date, solve, solve_moves},
+    };
+
+    use super::*;
+
+    /// Creates stats with solves of given times in seconds, the oldest first
+    fn stats(secs: &[u64]) -> Stats {
+        let mut stats = Stats::default();
+        for (i, secs) in secs.iter().enumerate() {
+            let stat = solve(Duration::from_secs(*secs));
+            stats.add(stat.with_date(date(i as i64)));
+        }
+        stats
+    }
+
+    /// Gets the time of the best solve
+    fn best(stats: &Stats) -> Option<Duration> {
+        stats.best().as_ref().map(|b| b.time())
+    }
+
+    #[test]
+    fn dnf_recomputes_best() {
+        let mut stats = stats(&[7, 3, 5, 8, 6]);
+        assert_eq!(best(&stats), Some(Duration::from_secs(3)));
+        assert_eq!(
+            stats.best_average(Average::Ao5),
+            Some(Duration::from_secs(6))
+        );
+
+        let id = stats.position(stats[3].date()).unwrap();
+        stats.set_penalty(id, Penalty::Dnf);
+        assert_eq!(best(&stats), Some(Duration::from_secs(5)));
+        assert_eq!(
+            stats.best_average(Average::Ao5),
+            Some(Duration::from_secs(7))
+        );
+
+        stats.set_penalty(id, Penalty::None);
+        assert_eq!(best(&stats), Some(Duration::from_secs(3)));
+    }
+
+    #[test]
+    fn plus_two_recomputes_best() {
+        let mut stats = stats(&[4, 5, 7]);
+        stats.set_penalty(2, Penalty::PlusTwo);
+        assert_eq!(best(&stats), Some(Duration::from_secs(5)));
+        assert_eq!(
+            stats.record(Record::Single),
+            Some(RecordValue::Time(Duration::from_secs(5)))
+        );
+        assert_eq!(
+            stats.best_average(Average::Mo3),
+            Some(Duration::from_secs(6))
+        );
+    }
+
+    #[test]
+    fn remove_recomputes_best() {
+        let mut stats = stats(&[7, 3, 5, 8, 6, 9]);
+        assert_eq!(
+            stats.best_average(Average::Ao5),
+            Some(Duration::from_secs(6))
+        );
+
+        let removed = stats.remove(4).unwrap();
+        assert_eq!(removed.time(), Duration::from_secs(3));
+        assert_eq!(stats.solves().len(), 5);
+        assert_eq!(best(&stats), Some(Duration::from_secs(5)));
+        assert_eq!(
+            stats.best_average(Average::Ao5),
+            Some(Duration::from_secs(7))
+        );
+        assert!(stats.remove(5).is_none());
+    }
+
+    #[test]
+    fn add_returns_improved_records() {
+        let mut stats = stats(&[7]);
+        assert_eq!(stats.pb_history().len(), 1);
+
+        let stat = stats[0].clone().with_date(Utc::now());
+        assert!(stats.add(stat).is_empty());
+
+        let time = Duration::from_secs(5);
+        let pbs = stats.add(solve(time));
+        assert_eq!(pbs.len(), 1);
+        assert_eq!(pbs[0].record, Record::Single);
+        assert_eq!(
+            pbs[0].previous,
+            Some(RecordValue::Time(Duration::from_secs(7)))
+        );
+        // The first mo3 is kept in the history, but it isn't an improvement
+        let last = stats.pb_history().last().unwrap();
+        assert_eq!(last.record, Record::Average(Average::Mo3));
+        assert_eq!(last.previous, None);
+        assert_eq!(stats.pb_history().len(), 3);
+    }
+
+    /// Imported solves without recorded moves aren't the fewest moves
+    #[test]
+    fn solves_without_moves_are_skipped() {
+        let mut stats = Stats::default();
+        let times = [(5, 20), (4, 0), (6, 30)];
+        for (i, (secs, moves)) in times.into_iter().enumerate() {
+            let stat = solve_moves(Duration::from_secs(secs), moves);
+            stats.add(stat.with_date(date(i as i64)));
+        }
+
+        assert_eq!(stats.record(Record::Moves), Some(RecordValue::Moves(20)));
+        assert_eq!(stats.fewest_moves().map(|s| s.metrics().mtm), Some(20));
+        assert_eq!(stats.highest_mps().map(|s| s.metrics().mtm), Some(30));
+        let moves = stats.pb_history().iter().filter(|pb| {
+            pb.record == Record::Moves && pb.value == RecordValue::Moves(0)
+        });
+        assert_eq!(moves.count(), 0);
+
+        let order = SortKey::Moves.order(stats.solves());
+        let moves: Vec<_> =
+            order.iter().map(|id| stats[*id].metrics().mtm).collect();
+        assert_eq!(moves, [20, 30, 0]);
+    }
+
+    /// Deleting the solves or changing their penalty doesn't rewrite the
+    /// personal bests history
+    #[test]
+    fn history_is_kept() {
+        let mut stats = stats(&[7, 3, 5]);
+        let history = stats.pb_history().to_vec();
+        let singles = history.iter().filter(|pb| pb.record == Record::Single);
+        assert_eq!(singles.count(), 2);
+
+        stats.set_penalty(1, Penalty::Dnf);
+        stats.remove(0);
+        assert_eq!(stats.pb_history(), history);
+
+        let json = stats.to_json().unwrap();
+        let loaded = Stats::from_json(&json, Vec2::new(3, 3)).unwrap();
+        assert_eq!(loaded.pb_history(), history);
+    }
+
+    /// Imported solve faster than the personal best is added to the history
+    #[test]
+    fn merge_adds_improved_records() {
+        let mut stats = stats(&[7, 5]);
+        let len = stats.pb_history().len();
+
+        let stat = solve(Duration::from_secs(3)).with_date(date(2));
+        assert_eq!(stats.merge(vec![stat.clone(), stat]).len(), 1);
+        // The single and the first mo3 are added
+        let added = &stats.pb_history()[len..];
+        assert_eq!(added.len(), 2);
+        assert_eq!(added[0].record, Record::Single);
+        assert_eq!(added[0].value, RecordValue::Time(Duration::from_secs(3)));
+        assert_eq!(added[0].date, date(2));
+        assert_eq!(stats.record(Record::Single), Some(added[0].value));
+    }
+
+    /// Stats saved before the history was kept get it built from the solves
+    #[test]
+    fn history_is_built_for_old_stats() {
+        let stats = stats(&[7, 3, 5]);
+        let mut value = serde_json::to_value(&stats).unwrap();
+        value.as_object_mut().unwrap().remove("records");
+
+        let loaded =
+            Stats::from_json(&value.to_string(), Vec2::new(3, 3)).unwrap();
+        assert_eq!(loaded.pb_history(), stats.pb_history());
     }
 }
@@ -0,0 +1,453 @@
+use std::{
+    cell::RefCell, fs::read_dir, io::ErrorKind, path::PathBuf, time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use termint::geometry::Vec2;
+
+use crate::{
+    error::Error,
+    file,
+    stats::{
+        average::Average,
+        records::PersonalBest,
+        session::SessionChange,
+        sort::SortKey,
+        stat::{Penalty, Stat},
+        stats_struct::Stats,
+    },
+};
+
+use super::{supported_size, Storage};
+
+/// Storage saving stats of each board size to separate JSON file. Last
+/// loaded stats are cached, so the paged queries don't read the file.
+#[derive(Debug)]
+pub struct JsonStorage {
+    dir: PathBuf,
+    cache: RefCell<Option<(Vec2, Stats)>>,
+    /// Order of the session solves last sorted by the key other than date
+    order: RefCell<Option<(String, SortKey, Vec<usize>)>>,
+}
+
+impl JsonStorage {
+    /// Creates new [`JsonStorage`] in the stats directory
+    pub fn new() -> Result<Self, Error> {
+        Ok(Self::with_dir(Stats::get_dir()?))
+    }
+
+    /// Creates new [`JsonStorage`] saving the files to the given directory
+    pub fn with_dir(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            cache: RefCell::new(None),
+            order: RefCell::new(None),
+        }
+    }
+
+    /// Locks the file of board with given size, reloads the stats, applies
+    /// given change and saves them atomically, keeping backups of the
+    /// previous versions of the file. The given stats are replaced by the
+    /// saved ones, so the changes saved by other running instances are
+    /// never overwritten. The active session of the given stats stays
+    /// active.
+    fn change<T>(
+        &self,
+        size: &Vec2,
+        stats: &mut Stats,
+        change: impl FnOnce(&mut Stats) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let path = self.get_file(size);
+        let _lock = file::lock(&path)?;
+
+        let mut saved = self.read(size)?;
+        saved.activate(stats.session().name());
+        let res = change(&mut saved)?;
+        file::save(&path, saved.to_json()?.as_bytes())?;
+
+        *stats = saved.clone();
+        self.set_cache(size, saved);
+        Ok(res)
+    }
+
+    /// Caches given stats of board with given size
+    fn set_cache(&self, size: &Vec2, stats: Stats) {
+        self.cache.replace(Some((*size, stats)));
+        self.order.replace(None);
+    }
+
+    /// Gets the stats of board with given size from the cache, loading them
+    /// when they aren't cached
+    fn cached<T>(
+        &self,
+        size: &Vec2,
+        get: impl FnOnce(&Stats) -> T,
+    ) -> Result<T, Error> {
+        let cached =
+            self.cache.borrow().as_ref().is_some_and(|(s, _)| s == size);
+        if !cached {
+            self.load(size)?;
+        }
+
+        let cache = self.cache.borrow();
+        let (_, stats) = cache.as_ref().ok_or("stats cache is empty")?;
+        Ok(get(stats))
+    }
+
+    /// Gets the solves of the session with given name and their indices
+    /// sorted by given key. The order is cached, so it isn't sorted again
+    /// when scrolling the table.
+    fn sorted<T>(
+        &self,
+        size: &Vec2,
+        session: &str,
+        sort: SortKey,
+        get: impl FnOnce(&[Stat], &[usize]) -> T,
+    ) -> Result<T, Error> {
+        self.cached(size, |stats| {
+            let Some(session) =
+                stats.sessions().iter().find(|s| s.name() == session)
+            else {
+                return get(&[], &[]);
+            };
+            let solves = session.solves();
+
+            let mut order = self.order.borrow_mut();
+            let cached = order.as_ref().is_some_and(|(name, key, _)| {
+                name == session.name() && *key == sort
+            });
+            if !cached {
+                let sorted = sort.order(solves);
+                *order = Some((session.name().to_string(), sort, sorted));
+            }
+            let (_, _, order) = order.as_ref().expect("order is cached");
+            get(solves, order)
+        })
+    }
+
+    /// Loads the stats from the file without caching them
+    fn read(&self, size: &Vec2) -> Result<Stats, Error> {
+        let path = self.get_file(size);
+        match file::read(&path)? {
+            Some(text) => Stats::from_json(&text, *size).map_err(|e| {
+                Error::Msg(format!(
+                    "invalid stats file '{}': {e}\nFix the file or restore \
+                    its backup ('{0}.1' is the newest)",
+                    path.display()
+                ))
+            }),
+            None => Ok(Stats::default()),
+        }
+    }
+
+    /// Gets stats file
+    fn get_file(&self, size: &Vec2) -> PathBuf {
+        self.dir.join(format!("{}x{}.json", size.x, size.y))
+    }
+}
+
+impl Storage for JsonStorage {
+    /// Loads the stats, empty stats are used when the file doesn't exist.
+    /// Fails when the file can't be parsed, so it isn't overwritten by the
+    /// next save.
+    fn load(&self, size: &Vec2) -> Result<Stats, Error> {
+        let stats = self.read(size)?;
+        self.set_cache(size, stats.clone());
+        Ok(stats)
+    }
+
+    fn add_solve(
+        &self,
+        size: &Vec2,
+        stats: &mut Stats,
+        stat: Stat,
+    ) -> Result<Vec<PersonalBest>, Error> {
+        self.change(size, stats, |stats| Ok(stats.add(stat)))
+    }
+
+    fn set_penalty(
+        &self,
+        size: &Vec2,
+        stats: &mut Stats,
+        date: DateTime<Utc>,
+        penalty: Penalty,
+    ) -> Result<(), Error> {
+        self.change(size, stats, |stats| {
+            if let Some(id) = stats.position(date) {
+                stats.set_penalty(id, penalty);
+            }
+            Ok(())
+        })
+    }
+
+    fn remove_solve(
+        &self,
+        size: &Vec2,
+        stats: &mut Stats,
+        date: DateTime<Utc>,
+    ) -> Result<Option<Stat>, Error> {
+        self.change(size, stats, |stats| {
+            Ok(stats.position(date).and_then(|id| stats.remove(id)))
+        })
+    }
+
+    fn merge_solves(
+        &self,
+        size: &Vec2,
+        stats: &mut Stats,
+        solves: Vec<Stat>,
+    ) -> Result<Vec<Stat>, Error> {
+        self.change(size, stats, |stats| Ok(stats.merge(solves)))
+    }
+
+    fn change_session(
+        &self,
+        size: &Vec2,
+        stats: &mut Stats,
+        change: &SessionChange,
+    ) -> Result<(), Error> {
+        self.change(size, stats, |stats| stats.change_session(change))
+    }
+
+    fn sizes(&self) -> Result<Vec<Vec2>, Error> {
+        let dir = match read_dir(&self.dir) {
+            Ok(dir) => dir,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut sizes = Vec::new();
+        for entry in dir {
+            let name = entry?.file_name();
+            let Some(name) =
+                name.to_str().and_then(|n| n.strip_suffix(".json"))
+            else {
+                continue;
+            };
+            let Some((w, h)) = name.split_once('x') else {
+                continue;
+            };
+            if let (Ok(w), Ok(h)) = (w.parse(), h.parse()) {
+                let size = Vec2::new(w, h);
+                if supported_size(&size) {
+                    sizes.push(size);
+                }
+            }
+        }
+        sizes.sort_by_key(|s: &Vec2| (s.x * s.y, s.x));
+        Ok(sizes)
+    }
+
+    /// Reads the file without caching it, so the cached stats of the
+    /// current size are kept
+    fn dates(&self, size: &Vec2) -> Result<Vec<DateTime<Utc>>, Error> {
+        let stats = self.read(size)?;
+        let solves = stats.sessions().iter().flat_map(|s| s.solves());
+        Ok(solves.map(|s| s.date()).collect())
+    }
+
+    fn count(&self, size: &Vec2, session: &str) -> Result<usize, Error> {
+        self.cached(size, |stats| {
+            let session =
+                stats.sessions().iter().find(|s| s.name() == session);
+            session.map(|s| s.solves().len()).unwrap_or_default()
+        })
+    }
+
+    fn page(
+        &self,
+        size: &Vec2,
+        session: &str,
+        sort: SortKey,
+        offset: usize,
+        len: usize,
+    ) -> Result<Vec<(usize, Stat)>, Error> {
+        self.sorted(size, session, sort, |solves, order| {
+            let page = order.iter().skip(offset).take(len);
+            page.map(|id| (*id, solves[*id].clone())).collect()
+        })
+    }
+
+    fn position(
+        &self,
+        size: &Vec2,
+        session: &str,
+        sort: SortKey,
+        date: DateTime<Utc>,
+    ) -> Result<Option<usize>, Error> {
+        self.sorted(size, session, sort, |solves, order| {
+            order.iter().position(|id| solves[*id].date() == date)
+        })
+    }
+
+    fn best(&self, size: &Vec2) -> Result<Option<Stat>, Error> {
+        self.cached(size, |stats| stats.best().clone())
+    }
+
+    fn fewest_moves(&self, size: &Vec2) -> Result<Option<Stat>, Error> {
+        self.cached(size, |stats| stats.fewest_moves().cloned())
+    }
+
+    fn highest_mps(&self, size: &Vec2) -> Result<Option<Stat>, Error> {
+        self.cached(size, |stats| stats.highest_mps().cloned())
+    }
+
+    fn best_average(
+        &self,
+        size: &Vec2,
+        avg: Average,
+    ) -> Result<Option<Duration>, Error> {
+        self.cached(size, |stats| stats.all_time_average(avg))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs::{remove_dir_all, write},
+        thread,
+    };
+
+    use crate::test_util::{date, solve, test_dir};
+
+    use super::*;
+
+    const SIZE: Vec2 = Vec2 { x: 3, y: 3 };
+
+    #[test]
+    fn add_merges_other_instance() {
+        let dir = test_dir("json-merge");
+        let first = JsonStorage::with_dir(dir.clone());
+        let second = JsonStorage::with_dir(dir.clone());
+        let mut first_stats = first.load(&SIZE).unwrap();
+        let mut second_stats = second.load(&SIZE).unwrap();
+
+        let stat = solve(Duration::from_millis(1000)).with_date(date(0));
+        first.add_solve(&SIZE, &mut first_stats, stat).unwrap();
+        let stat = solve(Duration::from_millis(2000)).with_date(date(1));
+        second.add_solve(&SIZE, &mut second_stats, stat).unwrap();
+
+        assert_eq!(second_stats.solves().len(), 2);
+        let stats = JsonStorage::with_dir(dir.clone()).load(&SIZE).unwrap();
+        assert_eq!(stats.solves().len(), 2);
+        assert_eq!(best(&stats), Some(Duration::from_millis(1000)));
+        remove_dir_all(dir).unwrap();
+    }
+
+    /// Sessions switched by other instance don't change the session the
+    /// solve is added to
+    #[test]
+    fn add_keeps_active_session() {
+        let dir = test_dir("json-session");
+        let first = JsonStorage::with_dir(dir.clone());
+        let second = JsonStorage::with_dir(dir.clone());
+        let mut first_stats = first.load(&SIZE).unwrap();
+        let mut second_stats = second.load(&SIZE).unwrap();
+
+        let change = SessionChange::New("other".into());
+        second
+            .change_session(&SIZE, &mut second_stats, &change)
+            .unwrap();
+        let stat = solve(Duration::from_secs(1));
+        first.add_solve(&SIZE, &mut first_stats, stat).unwrap();
+
+        assert_eq!(first_stats.session().name(), "default");
+        assert_eq!(first_stats.solves().len(), 1);
+        assert_eq!(first_stats.sessions().len(), 2);
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn concurrent_adds_keep_all_solves() {
+        let dir = test_dir("json-concurrent");
+        let threads: Vec<_> = (0..4)
+            .map(|t| {
+                let dir = dir.clone();
+                thread::spawn(move || {
+                    let storage = JsonStorage::with_dir(dir);
+                    let mut stats = storage.load(&SIZE).unwrap();
+                    for i in 0..5 {
+                        let stat = solve(Duration::from_secs(1))
+                            .with_date(date(t * 10 + i));
+                        storage.add_solve(&SIZE, &mut stats, stat).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        let stats = JsonStorage::with_dir(dir.clone()).load(&SIZE).unwrap();
+        assert_eq!(stats.solves().len(), 20);
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn invalid_file_isnt_overwritten() {
+        let dir = test_dir("json-invalid");
+        let storage = JsonStorage::with_dir(dir.clone());
+        let path = storage.get_file(&SIZE);
+        file::save(&path, b"{ invalid").unwrap();
+
+        let mut stats = Stats::default();
+        let stat = solve(Duration::from_secs(1));
+        let res = storage.add_solve(&SIZE, &mut stats, stat);
+        assert!(res.is_err());
+        assert_eq!(file::read(&path).unwrap().as_deref(), Some("{ invalid"));
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn pages_follow_changes() {
+        let dir = test_dir("json-page");
+        let storage = JsonStorage::with_dir(dir.clone());
+        let mut stats = storage.load(&SIZE).unwrap();
+        for (i, ms) in [3000, 1000, 2000].into_iter().enumerate() {
+            let stat =
+                solve(Duration::from_millis(ms)).with_date(date(i as i64));
+            storage.add_solve(&SIZE, &mut stats, stat).unwrap();
+        }
+
+        let times = |sort| {
+            let page = storage.page(&SIZE, "default", sort, 0, 10).unwrap();
+            page.iter()
+                .map(|(_, s)| s.time().as_millis())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(times(SortKey::Date), [2000, 1000, 3000]);
+        assert_eq!(times(SortKey::Time), [1000, 2000, 3000]);
+
+        let date = date(1);
+        let pos = storage.position(&SIZE, "default", SortKey::Time, date);
+        assert_eq!(pos.unwrap(), Some(0));
+        storage
+            .set_penalty(&SIZE, &mut stats, date, Penalty::Dnf)
+            .unwrap();
+        assert_eq!(times(SortKey::Time), [2000, 3000, 1000]);
+
+        let removed = storage.remove_solve(&SIZE, &mut stats, date).unwrap();
+        assert_eq!(removed.map(|s| s.date()), Some(date));
+        assert_eq!(times(SortKey::Time), [2000, 3000]);
+        assert_eq!(storage.count(&SIZE, "default").unwrap(), 2);
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn unsupported_sizes_are_skipped() {
+        let dir = test_dir("json-sizes");
+        let names = ["4294967296x4294967296", "0x0", "2x37", "5x4", "4x4"];
+        for name in names {
+            write(dir.join(format!("{name}.json")), "{}").unwrap();
+        }
+        let storage = JsonStorage::with_dir(dir.clone());
+        let sizes = storage.sizes().unwrap();
+        assert_eq!(sizes, [Vec2::new(4, 4), Vec2::new(5, 4)]);
+        remove_dir_all(dir).unwrap();
+    }
+
+    /// Gets the time of the best solve
+    fn best(stats: &Stats) -> Option<Duration> {
+        stats.best().as_ref().map(|b| b.time())
+    }
+}
@@ -0,0 +1,156 @@
+use std::{fmt::Debug, time::Duration};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use termint::geometry::Vec2;
+
+use crate::{board::board_struct::MAX_SIZE, error::Error};
+
+use super::{
+    average::Average,
+    records::PersonalBest,
+    session::SessionChange,
+    sort::SortKey,
+    stat::{Penalty, Stat},
+    stats_struct::Stats,
+};
+
+pub mod json;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+/// Kind of the storage the stats are saved in
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageKind {
+    /// JSON file for each board size
+    #[default]
+    Json,
+    /// SQLite database with solves of all the board sizes
+    Sqlite,
+}
+
+/// Storage of the stats of all the board sizes. Changes are saved by the
+/// methods changing only the affected solves, which apply the change to the
+/// given loaded stats as well.
+pub trait Storage: Debug {
+    /// Loads the stats of board with given size. Only the solves of the
+    /// active session have to be loaded, the solves of the other sessions
+    /// are read by [`Storage::page`].
+    fn load(&self, size: &Vec2) -> Result<Stats, Error>;
+
+    /// Adds given solve to the active session of the stats. Returns the
+    /// improved personal bests.
+    fn add_solve(
+        &self,
+        size: &Vec2,
+        stats: &mut Stats,
+        stat: Stat,
+    ) -> Result<Vec<PersonalBest>, Error>;
+
+    /// Sets penalty of the solve with given date in the active session
+    fn set_penalty(
+        &self,
+        size: &Vec2,
+        stats: &mut Stats,
+        date: DateTime<Utc>,
+        penalty: Penalty,
+    ) -> Result<(), Error>;
+
+    /// Removes the solve with given date from the active session. Returns
+    /// the removed solve.
+    fn remove_solve(
+        &self,
+        size: &Vec2,
+        stats: &mut Stats,
+        date: DateTime<Utc>,
+    ) -> Result<Option<Stat>, Error>;
+
+    /// Adds given solves to the active session, skipping solves with the
+    /// same date as already saved solve. Returns the added solves.
+    fn merge_solves(
+        &self,
+        size: &Vec2,
+        stats: &mut Stats,
+        solves: Vec<Stat>,
+    ) -> Result<Vec<Stat>, Error>;
+
+    /// Applies given change of the sessions
+    fn change_session(
+        &self,
+        size: &Vec2,
+        stats: &mut Stats,
+        change: &SessionChange,
+    ) -> Result<(), Error>;
+
+    /// Gets sizes of all the boards with saved stats, sorted by the number
+    /// of cells
+    fn sizes(&self) -> Result<Vec<Vec2>, Error>;
+
+    /// Gets dates of all the solves of board with given size
+    fn dates(&self, size: &Vec2) -> Result<Vec<DateTime<Utc>>, Error>;
+
+    /// Gets number of solves in the session with given name
+    fn count(&self, size: &Vec2, session: &str) -> Result<usize, Error>;
+
+    /// Gets at most `len` solves of the session with given name sorted by
+    /// given key, skipping `offset` first solves. Each solve is paired with
+    /// its index in the session solves, which are the newest first.
+    fn page(
+        &self,
+        size: &Vec2,
+        session: &str,
+        sort: SortKey,
+        offset: usize,
+        len: usize,
+    ) -> Result<Vec<(usize, Stat)>, Error>;
+
+    /// Gets position of the solve with given date in the session with given
+    /// name sorted by given key
+    fn position(
+        &self,
+        size: &Vec2,
+        session: &str,
+        sort: SortKey,
+        date: DateTime<Utc>,
+    ) -> Result<Option<usize>, Error>;
+
+    /// Gets all-time best solve
+    fn best(&self, size: &Vec2) -> Result<Option<Stat>, Error>;
+
+    /// Gets all-time solve with the fewest moves
+    fn fewest_moves(&self, size: &Vec2) -> Result<Option<Stat>, Error>;
+
+    /// Gets all-time solve with the highest moves per second
+    fn highest_mps(&self, size: &Vec2) -> Result<Option<Stat>, Error>;
+
+    /// Gets all-time best value of the given average
+    fn best_average(
+        &self,
+        size: &Vec2,
+        avg: Average,
+    ) -> Result<Option<Duration>, Error>;
+}
+
+/// Checks whether the board with given size is supported, so files and rows
+/// of other sizes are ignored
+fn supported_size(size: &Vec2) -> bool {
+    (2..=MAX_SIZE).contains(&size.x) && (2..=MAX_SIZE).contains(&size.y)
+}
+
+/// Opens the storage of given kind
+pub fn open(kind: StorageKind) -> Result<Box<dyn Storage>, Error> {
+    match kind {
+        StorageKind::Json => Ok(Box::new(json::JsonStorage::new()?)),
+        #[cfg(feature = "sqlite")]
+        StorageKind::Sqlite => Ok(Box::new(sqlite::SqliteStorage::open()?)),
+        #[cfg(not(feature = "sqlite"))]
+        StorageKind::Sqlite => Err(
+            "SQLite storage isn't supported, loopover has to be built with \
+            the sqlite feature"
+                .into(),
+        ),
+    }
+}
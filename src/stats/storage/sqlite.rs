@@ -0,0 +1,1313 @@
+use std::{
+    cell::RefCell, collections::VecDeque, fs::create_dir_all, path::PathBuf,
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use rusqlite::{
+    params, Connection, OptionalExtension, Transaction, TransactionBehavior,
+};
+use termint::geometry::Vec2;
+
+use crate::{
+    error::Error,
+    stats::{
+        average::Average,
+        records::{PersonalBest, Record, RecordValue},
+        session::{Session, SessionChange},
+        sort::SortKey,
+        stat::{Penalty, Stat},
+        stats_struct::Stats,
+    },
+};
+
+use super::{json::JsonStorage, supported_size, Storage};
+
+/// Time to wait for the database locked by other running instance
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Version of the database schema, saved as the database user version
+const SCHEMA_VERSION: i64 = 1;
+
+/// Schema of the stats database
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS sizes (
+    width INTEGER NOT NULL,
+    height INTEGER NOT NULL,
+    active TEXT NOT NULL,
+    PRIMARY KEY (width, height)
+);
+CREATE TABLE IF NOT EXISTS sessions (
+    width INTEGER NOT NULL,
+    height INTEGER NOT NULL,
+    id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    created INTEGER NOT NULL,
+    PRIMARY KEY (width, height, name)
+);
+CREATE TABLE IF NOT EXISTS solves (
+    width INTEGER NOT NULL,
+    height INTEGER NOT NULL,
+    session TEXT NOT NULL,
+    date INTEGER NOT NULL,
+    time INTEGER,
+    result INTEGER,
+    moves INTEGER NOT NULL,
+    mps REAL NOT NULL,
+    data TEXT NOT NULL,
+    UNIQUE (width, height, date)
+);
+CREATE INDEX IF NOT EXISTS solves_session
+    ON solves (width, height, session, date);
+CREATE INDEX IF NOT EXISTS solves_time
+    ON solves (width, height, session, time IS NULL, time, date DESC);
+CREATE INDEX IF NOT EXISTS solves_moves
+    ON solves (width, height, session, moves = 0, moves, date DESC);
+CREATE INDEX IF NOT EXISTS solves_mps
+    ON solves (width, height, session, mps DESC, date DESC);
+CREATE INDEX IF NOT EXISTS solves_result ON solves (width, height, result);
+CREATE TABLE IF NOT EXISTS averages (
+    width INTEGER NOT NULL,
+    height INTEGER NOT NULL,
+    session TEXT NOT NULL,
+    count INTEGER NOT NULL,
+    best INTEGER NOT NULL,
+    PRIMARY KEY (width, height, session, count)
+);
+CREATE TABLE IF NOT EXISTS records (
+    width INTEGER NOT NULL,
+    height INTEGER NOT NULL,
+    date INTEGER NOT NULL,
+    data TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS records_size ON records (width, height, date);
+";
+
+/// Conditions and order of the solves selecting the all-time best solve
+const BEST_SOLVE: &str = "result IS NOT NULL ORDER BY result, date";
+
+/// Conditions and order of the solves selecting the all-time solve with the
+/// fewest moves, solves without recorded moves are skipped
+const FEWEST_MOVES: &str =
+    "result IS NOT NULL AND moves > 0 ORDER BY moves, date";
+
+/// Conditions and order of the solves selecting the all-time solve with the
+/// highest moves per second, solves without recorded moves are skipped
+const HIGHEST_MPS: &str =
+    "result IS NOT NULL AND moves > 0 ORDER BY mps DESC, date";
+
+/// Storage saving stats of all the board sizes to SQLite database. Solves
+/// are stored as rows, so the paged queries read only the requested solves
+/// and the changes write only the changed solves. Stats of the sizes not yet
+/// in the database are loaded from the JSON files.
+#[derive(Debug)]
+pub struct SqliteStorage {
+    conn: RefCell<Connection>,
+    json: JsonStorage,
+}
+
+impl SqliteStorage {
+    /// Opens the stats database, creating it when it doesn't exist
+    pub fn open() -> Result<Self, Error> {
+        SqliteStorage::with_dir(Stats::get_dir()?)
+    }
+
+    /// Opens the stats database in the given directory, creating it when it
+    /// doesn't exist
+    pub fn with_dir(dir: PathBuf) -> Result<Self, Error> {
+        create_dir_all(&dir)?;
+
+        let mut conn = Connection::open(dir.join("stats.db"))?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        let tx =
+            conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let version: i64 =
+            tx.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version > SCHEMA_VERSION {
+            return Err(format!(
+                "stats database version {version} is newer than supported \
+                version {SCHEMA_VERSION}, update loopover to use it"
+            )
+            .into());
+        }
+        if version < SCHEMA_VERSION {
+            tx.execute_batch(SCHEMA)?;
+            tx.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        }
+        tx.commit()?;
+
+        Ok(Self {
+            conn: RefCell::new(conn),
+            json: JsonStorage::with_dir(dir),
+        })
+    }
+
+    /// Applies given change of the stats of board with given size in a
+    /// transaction, which locks the database, so the changes of other
+    /// running instances are never overwritten. Stats of the size not yet
+    /// in the database are moved there from the JSON file first.
+    fn change<T>(
+        &self,
+        size: &Vec2,
+        change: impl FnOnce(&Transaction) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let mut conn = self.conn.borrow_mut();
+        let tx =
+            conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        if SqliteStorage::read_active(&tx, size)?.is_none() {
+            SqliteStorage::insert(&tx, size, &self.json.load(size)?)?;
+        }
+        let res = change(&tx)?;
+        tx.commit()?;
+        Ok(res)
+    }
+
+    /// Loads the stats of board with given size using the given connection,
+    /// [`None`] when the size isn't in the database. Only the solves of the
+    /// active session are loaded, the other sessions are read by the paged
+    /// queries and the all-time bests are queried from the database.
+    fn read(conn: &Connection, size: &Vec2) -> Result<Option<Stats>, Error> {
+        let Some(active) = SqliteStorage::read_active(conn, size)? else {
+            return Ok(None);
+        };
+
+        let mut query = conn.prepare(
+            "SELECT name, created FROM sessions
+            WHERE width = ?1 AND height = ?2 ORDER BY id",
+        )?;
+        let sessions = query
+            .query_map(params![size.x, size.y], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut res = Vec::new();
+        for (name, created) in sessions {
+            let solves = match name == active {
+                true => SqliteStorage::solves(
+                    conn,
+                    size,
+                    &name,
+                    SortKey::Date,
+                    0,
+                    usize::MAX,
+                )?
+                .into_iter()
+                .map(|(_, stat)| stat)
+                .collect(),
+                false => Vec::new(),
+            };
+            res.push(
+                Session::with_solves(name, solves)
+                    .with_created(from_millis(created)),
+            );
+        }
+
+        let mut query = conn.prepare(
+            "SELECT data FROM records
+            WHERE width = ?1 AND height = ?2 ORDER BY date, rowid",
+        )?;
+        let records = query
+            .query_map(params![size.x, size.y], |row| row.get::<_, String>(0))?
+            .map(|data| Ok(serde_json::from_str(&data?)?))
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let active = res.iter().position(|s| s.name() == active);
+        Ok(Some(Stats::from_sessions(
+            res,
+            active.unwrap_or_default(),
+            records,
+            SqliteStorage::best_solve(conn, size, BEST_SOLVE)?,
+        )))
+    }
+
+    /// Rebuilds the personal bests history from the solves of all the
+    /// sessions of board with given size, not only the loaded ones
+    fn rebuild_records(
+        conn: &Connection,
+        size: &Vec2,
+    ) -> Result<Vec<PersonalBest>, Error> {
+        let mut query = conn.prepare(
+            "SELECT name FROM sessions
+            WHERE width = ?1 AND height = ?2 ORDER BY id",
+        )?;
+        let names = query
+            .query_map(params![size.x, size.y], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut sessions = Vec::new();
+        for name in names {
+            let solves = SqliteStorage::solves(
+                conn,
+                size,
+                &name,
+                SortKey::Date,
+                0,
+                usize::MAX,
+            )?;
+            let solves = solves.into_iter().map(|(_, stat)| stat).collect();
+            sessions.push(Session::with_solves(name, solves));
+        }
+
+        let mut stats = Stats::from_sessions(sessions, 0, Vec::new(), None);
+        stats.rebuild_records();
+        Ok(stats.pb_history().to_vec())
+    }
+
+    /// Gets the name of the active session of board with given size,
+    /// [`None`] when the size isn't in the database
+    fn read_active(
+        conn: &Connection,
+        size: &Vec2,
+    ) -> Result<Option<String>, Error> {
+        Ok(conn
+            .query_row(
+                "SELECT active FROM sizes WHERE width = ?1 AND height = ?2",
+                params![size.x, size.y],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// Gets sizes of all the boards in the database
+    fn db_sizes(conn: &Connection) -> Result<Vec<Vec2>, Error> {
+        let mut query = conn.prepare("SELECT width, height FROM sizes")?;
+        let sizes = query
+            .query_map([], |row| Ok(Vec2::new(row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(sizes)
+    }
+
+    /// Inserts the stats of board with given size, which isn't in the
+    /// database yet
+    fn insert(
+        tx: &Transaction,
+        size: &Vec2,
+        stats: &Stats,
+    ) -> Result<(), Error> {
+        tx.execute(
+            "INSERT INTO sizes (width, height, active) VALUES (?1, ?2, ?3)",
+            params![size.x, size.y, stats.session().name()],
+        )?;
+
+        let mut session_query = tx.prepare(
+            "INSERT INTO sessions (width, height, id, name, created)
+            VALUES (?1, ?2, ?3, ?4, ?5)",
+        )?;
+        let mut avg_query = tx.prepare(
+            "INSERT INTO averages (width, height, session, count, best)
+            VALUES (?1, ?2, ?3, ?4, ?5)",
+        )?;
+        for (id, session) in stats.sessions().iter().enumerate() {
+            session_query.execute(params![
+                size.x,
+                size.y,
+                id,
+                session.name(),
+                session.created().timestamp_millis(),
+            ])?;
+            for stat in session.solves() {
+                SqliteStorage::insert_solve(tx, size, session.name(), stat)?;
+            }
+            for avg in Average::ALL {
+                if let Some(best) = session.best_average(avg) {
+                    avg_query.execute(params![
+                        size.x,
+                        size.y,
+                        session.name(),
+                        avg.count(),
+                        to_micros(best),
+                    ])?;
+                }
+            }
+        }
+
+        SqliteStorage::insert_records(tx, size, stats.pb_history())
+    }
+
+    /// Inserts given solve to the session with given name
+    fn insert_solve(
+        tx: &Transaction,
+        size: &Vec2,
+        session: &str,
+        stat: &Stat,
+    ) -> Result<(), Error> {
+        let time = stat.final_time().map(to_micros);
+        let result = time.filter(|_| stat.is_better(None));
+        tx.prepare_cached(
+            "INSERT INTO solves
+            (width, height, session, date, time, result, moves, mps, data)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        )?
+        .execute(params![
+            size.x,
+            size.y,
+            session,
+            stat.date().timestamp_millis(),
+            time,
+            result,
+            stat.metrics().mtm,
+            stat.mps(),
+            serde_json::to_string(stat)?,
+        ])?;
+        Ok(())
+    }
+
+    /// Inserts given personal bests to the history
+    fn insert_records(
+        tx: &Transaction,
+        size: &Vec2,
+        records: &[PersonalBest],
+    ) -> Result<(), Error> {
+        let mut query = tx.prepare_cached(
+            "INSERT INTO records (width, height, date, data)
+            VALUES (?1, ?2, ?3, ?4)",
+        )?;
+        for pb in records {
+            query.execute(params![
+                size.x,
+                size.y,
+                pb.date.timestamp_millis(),
+                serde_json::to_string(pb)?,
+            ])?;
+        }
+        Ok(())
+    }
+
+    /// Creates session with given name when it doesn't exist, so solves can
+    /// be added to the session removed by other running instance
+    fn ensure_session(
+        tx: &Transaction,
+        size: &Vec2,
+        session: &Session,
+    ) -> Result<(), Error> {
+        tx.execute(
+            "INSERT OR IGNORE INTO sessions (width, height, id, name, created)
+            SELECT ?1, ?2, COALESCE(MAX(id), -1) + 1, ?3, ?4 FROM sessions
+            WHERE width = ?1 AND height = ?2",
+            params![
+                size.x,
+                size.y,
+                session.name(),
+                session.created().timestamp_millis(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Gets the solve with given date and the name of its session
+    fn find_solve(
+        tx: &Transaction,
+        size: &Vec2,
+        date: DateTime<Utc>,
+    ) -> Result<Option<(String, Stat)>, Error> {
+        let row: Option<(String, String)> = tx
+            .query_row(
+                "SELECT session, data FROM solves
+                WHERE width = ?1 AND height = ?2 AND date = ?3",
+                params![size.x, size.y, date.timestamp_millis()],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        let Some((session, data)) = row else {
+            return Ok(None);
+        };
+        let mut stat: Stat = serde_json::from_str(&data)?;
+        stat.fill_metrics(*size);
+        Ok(Some((session, stat)))
+    }
+
+    /// Gets the final times of at most `len` newest solves of the session
+    /// with given name, the newest first
+    fn times(
+        tx: &Transaction,
+        size: &Vec2,
+        session: &str,
+        len: usize,
+    ) -> Result<Vec<Option<Duration>>, Error> {
+        let mut query = tx.prepare_cached(
+            "SELECT time FROM solves
+            WHERE width = ?1 AND height = ?2 AND session = ?3
+            ORDER BY date DESC LIMIT ?4",
+        )?;
+        let len = i64::try_from(len).unwrap_or(-1);
+        let times = query
+            .query_map(params![size.x, size.y, session, len], |row| {
+                row.get::<_, Option<i64>>(0)
+            })?
+            .map(|time| time.map(|t| t.map(from_micros)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(times)
+    }
+
+    /// Updates the best averages of the session with given name by the
+    /// averages ending with its newest solve
+    fn add_averages(
+        tx: &Transaction,
+        size: &Vec2,
+        session: &str,
+    ) -> Result<(), Error> {
+        let len = Average::ALL.iter().map(|a| a.count()).max();
+        let times =
+            SqliteStorage::times(tx, size, session, len.unwrap_or_default())?;
+
+        let mut query = tx.prepare_cached(
+            "INSERT INTO averages (width, height, session, count, best)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            ON CONFLICT (width, height, session, count)
+            DO UPDATE SET best = MIN(best, excluded.best)",
+        )?;
+        for avg in Average::ALL {
+            if let Some(Some(cur)) = avg.compute(times.iter().copied()) {
+                query.execute(params![
+                    size.x,
+                    size.y,
+                    session,
+                    avg.count(),
+                    to_micros(cur),
+                ])?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Recomputes the best averages of the session with given name from
+    /// the final times of its solves. The times are streamed, so only the
+    /// times of the longest average are kept in memory.
+    fn recompute_averages(
+        tx: &Transaction,
+        size: &Vec2,
+        session: &str,
+    ) -> Result<(), Error> {
+        tx.execute(
+            "DELETE FROM averages
+            WHERE width = ?1 AND height = ?2 AND session = ?3",
+            params![size.x, size.y, session],
+        )?;
+
+        let len = Average::ALL.iter().map(|a| a.count()).max();
+        let len = len.unwrap_or_default();
+        let mut window = VecDeque::with_capacity(len);
+        let mut bests = [None; Average::ALL.len()];
+        let mut query = tx.prepare_cached(
+            "SELECT time FROM solves
+            WHERE width = ?1 AND height = ?2 AND session = ?3
+            ORDER BY date DESC",
+        )?;
+        let times = query
+            .query_map(params![size.x, size.y, session], |row| {
+                row.get::<_, Option<i64>>(0)
+            })?;
+        for time in times {
+            if window.len() == len {
+                window.pop_front();
+            }
+            window.push_back(time?.map(from_micros));
+            // Window ends with the oldest solve of each average
+            for (best, avg) in bests.iter_mut().zip(Average::ALL) {
+                let skip = window.len().saturating_sub(avg.count());
+                let cur = avg.compute(window.iter().skip(skip).copied());
+                if let Some(Some(cur)) = cur {
+                    *best = Some(best.map_or(cur, |b: Duration| b.min(cur)));
+                }
+            }
+        }
+
+        let mut query = tx.prepare_cached(
+            "INSERT INTO averages (width, height, session, count, best)
+            VALUES (?1, ?2, ?3, ?4, ?5)",
+        )?;
+        for (best, avg) in bests.into_iter().zip(Average::ALL) {
+            if let Some(best) = best {
+                query.execute(params![
+                    size.x,
+                    size.y,
+                    session,
+                    avg.count(),
+                    to_micros(best),
+                ])?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Gets the all-time value of each of [`Record::ALL`]
+    fn records(
+        conn: &Connection,
+        size: &Vec2,
+    ) -> Result<[Option<RecordValue>; Record::ALL.len()], Error> {
+        let mut values = [None; Record::ALL.len()];
+        for (value, record) in values.iter_mut().zip(Record::ALL) {
+            *value = match record {
+                Record::Single => conn
+                    .query_row(
+                        "SELECT MIN(result) FROM solves
+                        WHERE width = ?1 AND height = ?2",
+                        params![size.x, size.y],
+                        |row| row.get::<_, Option<i64>>(0),
+                    )?
+                    .map(|t| RecordValue::Time(from_micros(t))),
+                Record::Moves => conn
+                    .query_row(
+                        "SELECT MIN(moves) FROM solves
+                        WHERE width = ?1 AND height = ?2
+                            AND result IS NOT NULL AND moves > 0",
+                        params![size.x, size.y],
+                        |row| row.get::<_, Option<usize>>(0),
+                    )?
+                    .map(RecordValue::Moves),
+                Record::Average(avg) => {
+                    SqliteStorage::best_avg(conn, size, avg)?
+                        .map(RecordValue::Time)
+                }
+            };
+        }
+        Ok(values)
+    }
+
+    /// Gets the all-time best value of the given average
+    fn best_avg(
+        conn: &Connection,
+        size: &Vec2,
+        avg: Average,
+    ) -> Result<Option<Duration>, Error> {
+        let best: Option<i64> = conn.query_row(
+            "SELECT MIN(best) FROM averages
+            WHERE width = ?1 AND height = ?2 AND count = ?3",
+            params![size.x, size.y, avg.count()],
+            |row| row.get(0),
+        )?;
+        Ok(best.map(from_micros))
+    }
+
+    /// Gets the first solve of board with given size matching the given
+    /// conditions and order, such as [`BEST_SOLVE`]
+    fn best_solve(
+        conn: &Connection,
+        size: &Vec2,
+        filter: &str,
+    ) -> Result<Option<Stat>, Error> {
+        let data: Option<String> = conn
+            .prepare_cached(&format!(
+                "SELECT data FROM solves
+                WHERE width = ?1 AND height = ?2 AND {filter} LIMIT 1"
+            ))?
+            .query_row(params![size.x, size.y], |row| row.get(0))
+            .optional()?;
+        let Some(data) = data else {
+            return Ok(None);
+        };
+        let mut stat: Stat = serde_json::from_str(&data)?;
+        stat.fill_metrics(*size);
+        Ok(Some(stat))
+    }
+
+    /// Gets the solves of the session with given name sorted by given key,
+    /// each with its index in the session sorted by date
+    fn solves(
+        conn: &Connection,
+        size: &Vec2,
+        session: &str,
+        sort: SortKey,
+        offset: usize,
+        len: usize,
+    ) -> Result<Vec<(usize, Stat)>, Error> {
+        let mut query = conn.prepare_cached(&format!(
+            "SELECT data, ROW_NUMBER() OVER (ORDER BY date DESC) - 1
+            FROM solves
+            WHERE width = ?1 AND height = ?2 AND session = ?3
+            ORDER BY {} LIMIT ?4 OFFSET ?5",
+            order_by(sort)
+        ))?;
+        let len = i64::try_from(len).unwrap_or(-1);
+        let rows = query.query_map(
+            params![size.x, size.y, session, len, offset],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, usize>(1)?)),
+        )?;
+
+        let mut solves = Vec::new();
+        for row in rows {
+            let (data, id) = row?;
+            let mut stat: Stat = serde_json::from_str(&data)?;
+            stat.fill_metrics(*size);
+            solves.push((id, stat));
+        }
+        Ok(solves)
+    }
+
+    /// Checks whether the stats of board with given size are in the
+    /// database
+    fn contains(&self, size: &Vec2) -> Result<bool, Error> {
+        let conn = self.conn.borrow();
+        Ok(SqliteStorage::read_active(&conn, size)?.is_some())
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn load(&self, size: &Vec2) -> Result<Stats, Error> {
+        let conn = self.conn.borrow();
+        match SqliteStorage::read(&conn, size)? {
+            Some(stats) => Ok(stats),
+            None => self.json.load(size),
+        }
+    }
+
+    /// Inserts the solve and updates the best averages of its session by
+    /// the averages ending with it
+    fn add_solve(
+        &self,
+        size: &Vec2,
+        stats: &mut Stats,
+        stat: Stat,
+    ) -> Result<Vec<PersonalBest>, Error> {
+        let pbs = self.change(size, |tx| {
+            let session = stats.session();
+            SqliteStorage::ensure_session(tx, size, session)?;
+
+            let before = SqliteStorage::records(tx, size)?;
+            SqliteStorage::insert_solve(tx, size, session.name(), &stat)?;
+            SqliteStorage::add_averages(tx, size, session.name())?;
+            let after = SqliteStorage::records(tx, size)?;
+
+            let pbs = PersonalBest::improved(before, after, stat.date());
+            SqliteStorage::insert_records(tx, size, &pbs)?;
+            Ok(pbs)
+        })?;
+
+        stats.push(stat);
+        Ok(stats.add_records(pbs))
+    }
+
+    fn set_penalty(
+        &self,
+        size: &Vec2,
+        stats: &mut Stats,
+        date: DateTime<Utc>,
+        penalty: Penalty,
+    ) -> Result<(), Error> {
+        self.change(size, |tx| {
+            let Some((session, mut stat)) =
+                SqliteStorage::find_solve(tx, size, date)?
+            else {
+                return Ok(());
+            };
+
+            stat.set_penalty(penalty);
+            let time = stat.final_time().map(to_micros);
+            let result = time.filter(|_| stat.is_better(None));
+            tx.execute(
+                "UPDATE solves SET time = ?4, result = ?5, data = ?6
+                WHERE width = ?1 AND height = ?2 AND date = ?3",
+                params![
+                    size.x,
+                    size.y,
+                    date.timestamp_millis(),
+                    time,
+                    result,
+                    serde_json::to_string(&stat)?,
+                ],
+            )?;
+            SqliteStorage::recompute_averages(tx, size, &session)?;
+
+            if let Some(id) = stats.position(date) {
+                stats.set_penalty(id, penalty);
+            }
+            stats.set_best(SqliteStorage::best_solve(tx, size, BEST_SOLVE)?);
+            Ok(())
+        })
+    }
+
+    fn remove_solve(
+        &self,
+        size: &Vec2,
+        stats: &mut Stats,
+        date: DateTime<Utc>,
+    ) -> Result<Option<Stat>, Error> {
+        self.change(size, |tx| {
+            let Some((session, stat)) =
+                SqliteStorage::find_solve(tx, size, date)?
+            else {
+                return Ok(None);
+            };
+
+            tx.execute(
+                "DELETE FROM solves
+                WHERE width = ?1 AND height = ?2 AND date = ?3",
+                params![size.x, size.y, date.timestamp_millis()],
+            )?;
+            SqliteStorage::recompute_averages(tx, size, &session)?;
+
+            if let Some(id) = stats.position(date) {
+                stats.remove(id);
+            }
+            stats.set_best(SqliteStorage::best_solve(tx, size, BEST_SOLVE)?);
+            Ok(Some(stat))
+        })
+    }
+
+    fn merge_solves(
+        &self,
+        size: &Vec2,
+        stats: &mut Stats,
+        solves: Vec<Stat>,
+    ) -> Result<Vec<Stat>, Error> {
+        self.change(size, |tx| {
+            let session = stats.session().name().to_string();
+            SqliteStorage::ensure_session(tx, size, stats.session())?;
+
+            let before = SqliteStorage::records(tx, size)?;
+            let mut added = Vec::new();
+            for stat in solves {
+                let exists: bool = tx.query_row(
+                    "SELECT EXISTS (SELECT 1 FROM solves
+                    WHERE width = ?1 AND height = ?2 AND date = ?3)",
+                    params![size.x, size.y, stat.date().timestamp_millis()],
+                    |row| row.get(0),
+                )?;
+                if !exists {
+                    SqliteStorage::insert_solve(tx, size, &session, &stat)?;
+                    added.push(stat);
+                }
+            }
+            let Some(date) = added.iter().map(|s| s.date()).max() else {
+                return Ok(added);
+            };
+            SqliteStorage::recompute_averages(tx, size, &session)?;
+            let after = SqliteStorage::records(tx, size)?;
+
+            // The history is kept, unless it's built from the merged solves
+            let had_records = !stats.pb_history().is_empty();
+            stats.extend(added.clone());
+            stats.set_best(SqliteStorage::best_solve(tx, size, BEST_SOLVE)?);
+            if had_records {
+                let pbs = PersonalBest::improved(before, after, date);
+                SqliteStorage::insert_records(tx, size, &pbs)?;
+                stats.add_records(pbs);
+            } else {
+                let pbs = SqliteStorage::rebuild_records(tx, size)?;
+                SqliteStorage::insert_records(tx, size, &pbs)?;
+                stats.add_records(pbs);
+            }
+            Ok(added)
+        })
+    }
+
+    /// Changes copy of the loaded stats first, so the session names are
+    /// checked before changing the database. The loaded stats are replaced
+    /// only after the change is saved.
+    fn change_session(
+        &self,
+        size: &Vec2,
+        stats: &mut Stats,
+        change: &SessionChange,
+    ) -> Result<(), Error> {
+        let mut changed = stats.clone();
+        changed.change_session(change)?;
+        let loaded = self.change(size, |tx| {
+            let (w, h) = (size.x, size.y);
+            match change {
+                SessionChange::New(_) => {
+                    SqliteStorage::ensure_session(tx, size, changed.session())?
+                }
+                SessionChange::Rename(old, new) => {
+                    for (table, column) in [
+                        ("sessions", "name"),
+                        ("solves", "session"),
+                        ("averages", "session"),
+                    ] {
+                        tx.execute(
+                            &format!(
+                                "UPDATE {table} SET {column} = ?4
+                                WHERE width = ?1 AND height = ?2
+                                    AND {column} = ?3"
+                            ),
+                            params![w, h, old, new],
+                        )?;
+                    }
+                }
+                SessionChange::Switch(_) => {}
+                SessionChange::Merge(from, into) => {
+                    tx.execute(
+                        "UPDATE solves SET session = ?4
+                        WHERE width = ?1 AND height = ?2 AND session = ?3",
+                        params![w, h, from, into],
+                    )?;
+                    for (table, column) in
+                        [("sessions", "name"), ("averages", "session")]
+                    {
+                        tx.execute(
+                            &format!(
+                                "DELETE FROM {table}
+                                WHERE width = ?1 AND height = ?2
+                                    AND {column} = ?3"
+                            ),
+                            params![w, h, from],
+                        )?;
+                    }
+                    SqliteStorage::recompute_averages(tx, size, into)?;
+                }
+            }
+            tx.execute(
+                "UPDATE sizes SET active = ?3
+                WHERE width = ?1 AND height = ?2",
+                params![w, h, changed.session().name()],
+            )?;
+
+            // Solves of the session that became active aren't loaded yet
+            SqliteStorage::read(tx, size)
+        })?;
+        *stats = loaded.unwrap_or(changed);
+        Ok(())
+    }
+
+    fn sizes(&self) -> Result<Vec<Vec2>, Error> {
+        let mut sizes = SqliteStorage::db_sizes(&self.conn.borrow())?;
+        for size in self.json.sizes()? {
+            if !sizes.contains(&size) {
+                sizes.push(size);
+            }
+        }
+        sizes.retain(supported_size);
+        sizes.sort_by_key(|s| (s.x * s.y, s.x));
+        Ok(sizes)
+    }
+
+    fn dates(&self, size: &Vec2) -> Result<Vec<DateTime<Utc>>, Error> {
+        if !self.contains(size)? {
+            return self.json.dates(size);
+        }
+
+        let conn = self.conn.borrow();
+        let mut query = conn.prepare(
+            "SELECT date FROM solves WHERE width = ?1 AND height = ?2",
+        )?;
+        let dates = query
+            .query_map(params![size.x, size.y], |row| row.get(0))?
+            .map(|date| date.map(from_millis))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(dates)
+    }
+
+    fn count(&self, size: &Vec2, session: &str) -> Result<usize, Error> {
+        if !self.contains(size)? {
+            return self.json.count(size, session);
+        }
+
+        let count: i64 = self.conn.borrow().query_row(
+            "SELECT COUNT(*) FROM solves
+            WHERE width = ?1 AND height = ?2 AND session = ?3",
+            params![size.x, size.y, session],
+            |row| row.get(0),
+        )?;
+        Ok(count as usize)
+    }
+
+    fn page(
+        &self,
+        size: &Vec2,
+        session: &str,
+        sort: SortKey,
+        offset: usize,
+        len: usize,
+    ) -> Result<Vec<(usize, Stat)>, Error> {
+        if !self.contains(size)? {
+            return self.json.page(size, session, sort, offset, len);
+        }
+        let conn = self.conn.borrow();
+        SqliteStorage::solves(&conn, size, session, sort, offset, len)
+    }
+
+    fn position(
+        &self,
+        size: &Vec2,
+        session: &str,
+        sort: SortKey,
+        date: DateTime<Utc>,
+    ) -> Result<Option<usize>, Error> {
+        if !self.contains(size)? {
+            return self.json.position(size, session, sort, date);
+        }
+
+        let conn = self.conn.borrow();
+        let mut query = conn.prepare_cached(&format!(
+            "SELECT row FROM (
+                SELECT date, ROW_NUMBER() OVER (ORDER BY {}) - 1 AS row
+                FROM solves
+                WHERE width = ?1 AND height = ?2 AND session = ?3
+            ) WHERE date = ?4",
+            order_by(sort)
+        ))?;
+        let row: Option<usize> = query
+            .query_row(
+                params![size.x, size.y, session, date.timestamp_millis()],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(row)
+    }
+
+    fn best(&self, size: &Vec2) -> Result<Option<Stat>, Error> {
+        if !self.contains(size)? {
+            return self.json.best(size);
+        }
+        SqliteStorage::best_solve(&self.conn.borrow(), size, BEST_SOLVE)
+    }
+
+    fn fewest_moves(&self, size: &Vec2) -> Result<Option<Stat>, Error> {
+        if !self.contains(size)? {
+            return self.json.fewest_moves(size);
+        }
+        SqliteStorage::best_solve(&self.conn.borrow(), size, FEWEST_MOVES)
+    }
+
+    fn highest_mps(&self, size: &Vec2) -> Result<Option<Stat>, Error> {
+        if !self.contains(size)? {
+            return self.json.highest_mps(size);
+        }
+        SqliteStorage::best_solve(&self.conn.borrow(), size, HIGHEST_MPS)
+    }
+
+    fn best_average(
+        &self,
+        size: &Vec2,
+        avg: Average,
+    ) -> Result<Option<Duration>, Error> {
+        if !self.contains(size)? {
+            return self.json.best_average(size, avg);
+        }
+        SqliteStorage::best_avg(&self.conn.borrow(), size, avg)
+    }
+}
+
+/// Gets the `ORDER BY` clause sorting the solves by given key, matching the
+/// indices of the solves table and [`SortKey::order`]
+fn order_by(sort: SortKey) -> &'static str {
+    match sort {
+        SortKey::Date => "date DESC",
+        SortKey::Time => "time IS NULL, time, date DESC",
+        SortKey::Moves => "moves = 0, moves, date DESC",
+        SortKey::Mps => "mps DESC, date DESC",
+    }
+}
+
+/// Converts the duration to microseconds stored in the database
+fn to_micros(time: Duration) -> i64 {
+    time.as_micros() as i64
+}
+
+/// Converts microseconds stored in the database to the duration
+fn from_micros(micros: i64) -> Duration {
+    Duration::from_micros(micros.max(0) as u64)
+}
+
+/// Converts milliseconds since the epoch to the date
+fn from_millis(millis: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp_millis(millis).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::remove_dir_all;
+
+    use crate::test_util::{self, solve_moves, test_dir};
+
+    use super::*;
+
+    const SIZE: Vec2 = Vec2 { x: 3, y: 3 };
+
+    /// Adds solves with given times in milliseconds and numbers of moves,
+    /// each solved a second after the previous one
+    fn add(
+        storage: &SqliteStorage,
+        stats: &mut Stats,
+        solves: &[(u64, usize)],
+    ) {
+        for (ms, moves) in solves {
+            let cnt = storage.dates(&SIZE).unwrap().len();
+            let date = test_util::date(cnt as i64);
+            let stat = solve_moves(Duration::from_millis(*ms), *moves)
+                .with_date(date);
+            storage.add_solve(&SIZE, stats, stat).unwrap();
+        }
+    }
+
+    /// Gets the date of the loaded solve with given time in milliseconds
+    fn date(stats: &Stats, ms: u128) -> DateTime<Utc> {
+        let stat = stats.solves().iter().find(|s| s.time().as_millis() == ms);
+        stat.unwrap().date()
+    }
+
+    /// Gets the times of the solves on the page in milliseconds
+    fn times(storage: &SqliteStorage, sort: SortKey) -> Vec<u128> {
+        let page = storage.page(&SIZE, "default", sort, 0, 100).unwrap();
+        page.iter().map(|(_, s)| s.time().as_millis()).collect()
+    }
+
+    #[test]
+    fn pages_are_sorted() {
+        let dir = test_dir("sqlite-sort");
+        let storage = SqliteStorage::with_dir(dir.clone()).unwrap();
+        let mut stats = storage.load(&SIZE).unwrap();
+        add(&storage, &mut stats, &[(3000, 9), (1000, 12), (2000, 3)]);
+
+        // Sorted pages are the same as the order of the loaded solves
+        let sorts =
+            [SortKey::Date, SortKey::Time, SortKey::Moves, SortKey::Mps];
+        for sort in sorts {
+            let order = sort.order(stats.solves());
+            let expected: Vec<_> = order
+                .iter()
+                .map(|id| stats.solves()[*id].time().as_millis())
+                .collect();
+            assert_eq!(times(&storage, sort), expected);
+            let page = storage.page(&SIZE, "default", sort, 0, 100);
+            let ids: Vec<_> =
+                page.unwrap().iter().map(|(id, _)| *id).collect();
+            assert_eq!(ids, order);
+        }
+        assert_eq!(times(&storage, SortKey::Mps), [1000, 3000, 2000]);
+
+        let date = date(&stats, 1000);
+        let pos = storage.position(&SIZE, "default", SortKey::Time, date);
+        assert_eq!(pos.unwrap(), Some(0));
+        storage
+            .set_penalty(&SIZE, &mut stats, date, Penalty::Dnf)
+            .unwrap();
+        assert_eq!(times(&storage, SortKey::Time), [2000, 3000, 1000]);
+        let pos = storage.position(&SIZE, "default", SortKey::Time, date);
+        assert_eq!(pos.unwrap(), Some(2));
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn averages_follow_changes() {
+        let dir = test_dir("sqlite-averages");
+        let storage = SqliteStorage::with_dir(dir.clone()).unwrap();
+        let mut stats = storage.load(&SIZE).unwrap();
+        let solves = [5000, 4000, 7000, 1000, 6000, 3000].map(|ms| (ms, 5));
+        add(&storage, &mut stats, &solves);
+        // Times are saved in microseconds
+        let best = |storage: &SqliteStorage, stats: &Stats| {
+            let avg = storage.best_average(&SIZE, Average::Ao5).unwrap();
+            let expected = stats.all_time_average(Average::Ao5);
+            assert_eq!(avg, expected.map(|e| from_micros(to_micros(e))));
+        };
+        best(&storage, &stats);
+
+        let date = date(&stats, 1000);
+        storage
+            .set_penalty(&SIZE, &mut stats, date, Penalty::PlusTwo)
+            .unwrap();
+        best(&storage, &stats);
+
+        let removed = storage.remove_solve(&SIZE, &mut stats, date).unwrap();
+        assert_eq!(removed.map(|s| s.date()), Some(date));
+        best(&storage, &stats);
+        assert_eq!(storage.count(&SIZE, "default").unwrap(), 5);
+
+        let loaded = storage.load(&SIZE).unwrap();
+        assert_eq!(loaded.solves().len(), stats.solves().len());
+        assert_eq!(loaded.pb_history(), stats.pb_history());
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn add_returns_improved_records() {
+        let dir = test_dir("sqlite-records");
+        let storage = SqliteStorage::with_dir(dir.clone()).unwrap();
+        let mut stats = storage.load(&SIZE).unwrap();
+
+        let stat = solve_moves(Duration::from_millis(2000), 9)
+            .with_date(test_util::date(0));
+        let pbs = storage.add_solve(&SIZE, &mut stats, stat);
+        assert!(pbs.unwrap().is_empty());
+        let stat = solve_moves(Duration::from_millis(1000), 12)
+            .with_date(test_util::date(1));
+        let pbs = storage.add_solve(&SIZE, &mut stats, stat);
+        let records: Vec<_> = pbs.unwrap().iter().map(|p| p.record).collect();
+        assert_eq!(records, [Record::Single]);
+        assert_eq!(stats.pb_history().len(), 3);
+        remove_dir_all(dir).unwrap();
+    }
+
+    /// Imported solve faster than the personal best is added to the history
+    #[test]
+    fn merge_adds_improved_records() {
+        let dir = test_dir("sqlite-merge");
+        let storage = SqliteStorage::with_dir(dir.clone()).unwrap();
+        let mut stats = storage.load(&SIZE).unwrap();
+        add(&storage, &mut stats, &[(2000, 9)]);
+
+        let stat = solve_moves(Duration::from_millis(1000), 12)
+            .with_date(test_util::date(1));
+        let added = storage.merge_solves(&SIZE, &mut stats, vec![stat]);
+        assert_eq!(added.unwrap().len(), 1);
+        let last = stats.pb_history().last().unwrap();
+        assert_eq!(last.record, Record::Single);
+        assert_eq!(stats.record(Record::Single), Some(last.value));
+
+        let loaded = storage.load(&SIZE).unwrap();
+        assert_eq!(loaded.pb_history(), stats.pb_history());
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn merge_rebuilds_records_from_all_sessions() {
+        let dir = test_dir("sqlite-rebuild");
+        let storage = SqliteStorage::with_dir(dir.clone()).unwrap();
+        let mut stats = storage.load(&SIZE).unwrap();
+        add(&storage, &mut stats, &[(1000, 5)]);
+        let change = SessionChange::New("other".into());
+        storage.change_session(&SIZE, &mut stats, &change).unwrap();
+        let conn = storage.conn.borrow();
+        conn.execute("DELETE FROM records", []).unwrap();
+        drop(conn);
+
+        let mut stats = storage.load(&SIZE).unwrap();
+        assert!(stats.pb_history().is_empty());
+        let stat = solve_moves(Duration::from_millis(2000), 9)
+            .with_date(test_util::date(10));
+        storage.merge_solves(&SIZE, &mut stats, vec![stat]).unwrap();
+
+        let singles: Vec<_> = stats
+            .pb_history()
+            .iter()
+            .filter(|pb| pb.record == Record::Single)
+            .map(|pb| pb.value)
+            .collect();
+        let best = RecordValue::Time(Duration::from_millis(1000));
+        assert_eq!(singles, [best]);
+        let loaded = storage.load(&SIZE).unwrap();
+        assert_eq!(loaded.pb_history(), stats.pb_history());
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn failed_session_change_keeps_stats() {
+        let dir = test_dir("sqlite-failed-change");
+        let storage = SqliteStorage::with_dir(dir.clone()).unwrap();
+        let mut stats = storage.load(&SIZE).unwrap();
+        add(&storage, &mut stats, &[(1000, 5)]);
+        storage
+            .conn
+            .borrow()
+            .execute_batch(
+                "CREATE TRIGGER fail BEFORE UPDATE ON sizes
+                BEGIN SELECT RAISE(ABORT, 'failed'); END",
+            )
+            .unwrap();
+
+        let change = SessionChange::New("other".into());
+        assert!(storage.change_session(&SIZE, &mut stats, &change).is_err());
+        assert_eq!(stats.session().name(), "default");
+        assert_eq!(stats.sessions().len(), 1);
+        assert_eq!(stats.solves().len(), 1);
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn solves_without_moves_are_skipped() {
+        let dir = test_dir("sqlite-no-moves");
+        let storage = SqliteStorage::with_dir(dir.clone()).unwrap();
+        let mut stats = storage.load(&SIZE).unwrap();
+        add(&storage, &mut stats, &[(2000, 9), (1000, 0), (3000, 12)]);
+
+        assert_eq!(times(&storage, SortKey::Moves), [2000, 3000, 1000]);
+        let conn = storage.conn.borrow();
+        let records = SqliteStorage::records(&conn, &SIZE).unwrap();
+        let id = Record::ALL.iter().position(|r| *r == Record::Moves);
+        assert_eq!(records[id.unwrap()], Some(RecordValue::Moves(9)));
+        drop(conn);
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn sessions_are_changed() {
+        let dir = test_dir("sqlite-sessions");
+        let storage = SqliteStorage::with_dir(dir.clone()).unwrap();
+        let mut stats = storage.load(&SIZE).unwrap();
+        add(&storage, &mut stats, &[(1000, 5)]);
+
+        let changes = [
+            SessionChange::New("evening".into()),
+            SessionChange::Rename("evening".into(), "morning".into()),
+        ];
+        for change in changes.iter() {
+            storage.change_session(&SIZE, &mut stats, change).unwrap();
+        }
+        add(&storage, &mut stats, &[(2000, 5)]);
+        assert_eq!(storage.count(&SIZE, "morning").unwrap(), 1);
+
+        let merge = SessionChange::Merge("morning".into(), "default".into());
+        storage.change_session(&SIZE, &mut stats, &merge).unwrap();
+        assert_eq!(storage.count(&SIZE, "default").unwrap(), 2);
+
+        let loaded = storage.load(&SIZE).unwrap();
+        assert_eq!(loaded.sessions().len(), 1);
+        assert_eq!(loaded.session().name(), "default");
+        remove_dir_all(dir).unwrap();
+    }
+
+    /// Only the active session solves are loaded, the all-time bests are
+    /// queried from the database
+    #[test]
+    fn load_reads_active_session() {
+        let dir = test_dir("sqlite-load");
+        let storage = SqliteStorage::with_dir(dir.clone()).unwrap();
+        let mut stats = storage.load(&SIZE).unwrap();
+        add(&storage, &mut stats, &[(1000, 9), (2000, 12)]);
+        let change = SessionChange::New("other".into());
+        storage.change_session(&SIZE, &mut stats, &change).unwrap();
+        add(&storage, &mut stats, &[(3000, 15)]);
+
+        let loaded = storage.load(&SIZE).unwrap();
+        assert_eq!(loaded.session().name(), "other");
+        assert_eq!(loaded.solves().len(), 1);
+        assert!(loaded.sessions()[0].solves().is_empty());
+        assert_eq!(best(&loaded), Some(1000));
+        let moves = storage.fewest_moves(&SIZE).unwrap();
+        assert_eq!(moves.map(|s| s.metrics().mtm), Some(9));
+        let page = storage.page(&SIZE, "default", SortKey::Date, 0, 10);
+        assert_eq!(page.unwrap().len(), 2);
+
+        // Removing the best solve keeps the all-time best of other session
+        let switch = SessionChange::Switch("default".into());
+        storage.change_session(&SIZE, &mut stats, &switch).unwrap();
+        assert_eq!(stats.solves().len(), 2);
+        let removed = date(&stats, 2000);
+        storage.remove_solve(&SIZE, &mut stats, removed).unwrap();
+        let switch = SessionChange::Switch("other".into());
+        storage.change_session(&SIZE, &mut stats, &switch).unwrap();
+        let removed = date(&stats, 3000);
+        storage.remove_solve(&SIZE, &mut stats, removed).unwrap();
+        assert_eq!(best(&stats), Some(1000));
+        remove_dir_all(dir).unwrap();
+    }
+
+    /// Averages are recomputed from the streamed times
+    #[test]
+    fn recomputed_averages_match_session() {
+        let dir = test_dir("sqlite-recompute");
+        let storage = SqliteStorage::with_dir(dir.clone()).unwrap();
+        let mut stats = storage.load(&SIZE).unwrap();
+        let times =
+            [9, 3, 7, 4, 8, 2, 6, 5, 9, 1, 7, 3, 8].map(|s| (s * 100, 5));
+        add(&storage, &mut stats, &times);
+
+        let date = date(&stats, 100);
+        storage
+            .set_penalty(&SIZE, &mut stats, date, Penalty::Dnf)
+            .unwrap();
+        for avg in Average::ALL {
+            let best = storage.best_average(&SIZE, avg).unwrap();
+            let expected = stats.best_average(avg);
+            assert_eq!(best, expected.map(|e| from_micros(to_micros(e))));
+        }
+        remove_dir_all(dir).unwrap();
+    }
+
+    /// Gets the time of the best solve in milliseconds
+    fn best(stats: &Stats) -> Option<u128> {
+        stats.best().as_ref().map(|b| b.time().as_millis())
+    }
+}
@@ -0,0 +1,41 @@
+//! Helpers shared by the tests
+
+use std::{
+    env::temp_dir,
+    fs::{create_dir_all, remove_dir_all},
+    path::PathBuf,
+    process,
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use termint::geometry::Vec2;
+
+use crate::stats::{metrics::Metrics, stat::Stat};
+
+/// Creates solve with given time and without any recorded moves
+pub fn solve(time: Duration) -> Stat {
+    solve_moves(time, 0)
+}
+
+/// Creates solve with given time and number of moves
+pub fn solve_moves(time: Duration, moves: usize) -> Stat {
+    let metrics = Metrics {
+        mtm: moves,
+        ..Default::default()
+    };
+    Stat::new(time, metrics, String::new(), Vec2::new(0, 0), 0)
+}
+
+/// Gets date given number of seconds after the date of the first solve
+pub fn date(secs: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp(1_700_000_000 + secs, 0).unwrap()
+}
+
+/// Creates empty directory for the test with given unique name
+pub fn test_dir(name: &str) -> PathBuf {
+    let dir = temp_dir().join(format!("loopover-{}-{name}", process::id()));
+    _ = remove_dir_all(&dir);
+    create_dir_all(&dir).unwrap();
+    dir
+}
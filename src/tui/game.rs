@@ -4,15 +4,25 @@ use crossterm::event::{poll, KeyCode, KeyEvent, KeyModifiers};
 use termint::{
     enums::{Color, Modifier},
     geometry::{Constraint, Vec2},
-    widgets::{Layout, Paragraph, Spacer, StrSpanExtension, Widget},
+    widgets::{Layout, Paragraph, Spacer, StrSpanExtension, Text, Widget},
 };
 
 use crate::{
     app::{App, Screen, State},
+    board::board_struct::Board,
     error::Error,
-    stats::stat::Stat,
+    solver,
+    stats::{
+        activity,
+        average::Average,
+        metrics::{Metrics, MetricsCounter},
+        stat::{Penalty, Stat},
+    },
 };
 
+/// How long the new personal bests banner is shown
+const PB_BANNER_TIME: Duration = Duration::from_secs(5);
+
 //===========================================================================//
 //                           Public game methods                             //
 //===========================================================================//
@@ -26,6 +36,7 @@ impl App {
 
         let mut layout = Layout::vertical();
         layout.push(Spacer::new(), Constraint::Fill(1));
+        layout.push(self.render_new_pbs(), Constraint::Length(1));
         layout.push(
             board,
             Constraint::Length(self.board.height(&Vec2::new(0, 0))),
@@ -39,7 +50,20 @@ impl App {
 
     /// Handles key events for the game screen
     pub fn listen_game(&mut self, event: KeyEvent) -> Result<bool, Error> {
+        let block = KeyModifiers::CONTROL | KeyModifiers::SHIFT;
         let solved = match event.code {
+            KeyCode::Up if event.modifiers.contains(block) => {
+                self.resize_selection(|b| b.shrink_rows(), '^')
+            }
+            KeyCode::Down if event.modifiers.contains(block) => {
+                self.resize_selection(|b| b.grow_rows(), 'v')
+            }
+            KeyCode::Right if event.modifiers.contains(block) => {
+                self.resize_selection(|b| b.grow_columns(), '>')
+            }
+            KeyCode::Left if event.modifiers.contains(block) => {
+                self.resize_selection(|b| b.shrink_columns(), '<')
+            }
             KeyCode::Up => self.handle_move(
                 |s| s.board.up(),
                 |s| s.board.move_up(),
@@ -65,10 +89,15 @@ impl App {
                 event,
             )?,
             KeyCode::Enter => {
-                self.board.scramble();
-                self.state = State::Scrambled;
+                self.scramble();
                 true
             }
+            KeyCode::Char('h') => {
+                self.hint()?;
+                false
+            }
+            KeyCode::Char('z') => self.undo()?,
+            KeyCode::Char('y') => self.redo()?,
             KeyCode::Char('c')
                 if event.modifiers.contains(KeyModifiers::CONTROL) =>
             {
@@ -86,6 +115,43 @@ impl App {
     }
 }
 
+impl App {
+    /// Hides the new personal bests banner when it was shown long enough
+    pub fn pb_tick(&mut self) -> Result<(), Error> {
+        if let Some((_, time)) = self.new_pbs {
+            if time.elapsed() >= PB_BANNER_TIME {
+                self.new_pbs = None;
+                self.render()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Scrambles the board with the next seed or with random seed
+    pub fn scramble(&mut self) {
+        let seed = self.next_seed.take().unwrap_or_else(rand::random);
+        self.board.scramble(seed);
+        self.seed = Some(seed);
+        self.set_scrambled();
+    }
+
+    /// Loads the given scramble to the game
+    pub fn load_scramble(&mut self, board: Board) {
+        self.board.cells = board.cells;
+        self.seed = None;
+        self.set_scrambled();
+    }
+
+    /// Sets the game to the scrambled state
+    fn set_scrambled(&mut self) {
+        self.scramble = self.board.state();
+        self.board.hint = None;
+        self.hints = 0;
+        self.new_pbs = None;
+        self.state = State::Scrambled;
+    }
+}
+
 //===========================================================================//
 //                        Rendering helper methods                           //
 //===========================================================================//
@@ -100,7 +166,47 @@ impl App {
             Constraint::Min(0),
         );
 
+        if self.stats.sessions().len() > 1 {
+            layout.push(
+                format!("Session {}", self.stats.session().name())
+                    .fg(Color::Gray),
+                Constraint::Min(0),
+            );
+        }
+        let streak = self.activity.current_streak(activity::today());
+        if streak > 0 {
+            layout.push(
+                format!(
+                    "Streak {streak} days / longest {}",
+                    self.activity.longest_streak()
+                )
+                .fg(Color::Gray),
+                Constraint::Min(0),
+            );
+        }
+        self.simple_stats_avgs(&mut layout);
         self.simple_stats_moves(&mut layout);
+        if self.hints > 0 {
+            layout.push(
+                format!("{} hints used", self.hints).fg(Color::DarkYellow),
+                Constraint::Min(0),
+            );
+        }
+        if self.undos > 0 {
+            layout.push(
+                format!("{} undos", self.undos).fg(Color::DarkYellow),
+                Constraint::Min(0),
+            );
+        }
+        match self.seed {
+            Some(seed) => layout.push(
+                format!("Seed {seed}").fg(Color::Gray),
+                Constraint::Min(0),
+            ),
+            None if !self.scramble.is_empty() => layout
+                .push("Custom scramble".fg(Color::Gray), Constraint::Min(0)),
+            None => {}
+        }
 
         if let Some(best) = self.stats.best() {
             layout.push(self.simple_stats_best(best), Constraint::Min(0));
@@ -111,15 +217,40 @@ impl App {
         layout
     }
 
+    /// Adds current and best value of each average with enough solves
+    fn simple_stats_avgs(&self, layout: &mut Layout) {
+        for avg in Average::ALL {
+            let Some(cur) = self.stats.average(avg, 0) else {
+                continue;
+            };
+            let best = self.stats.best_average(avg).or(cur);
+            let p = Paragraph::new(vec![
+                format!("{avg:<5}").fg(Color::Gray).into(),
+                format_secs(cur).fg(Color::White).into(),
+                format_secs(best).fg(Color::Green).into(),
+            ])
+            .separator(" ");
+            layout.push(p, Constraint::Min(0));
+        }
+    }
+
     /// Gets moves count and moves per second
     fn simple_stats_moves(&self, layout: &mut Layout) {
+        let metrics = self.metrics.metrics();
         let mps = match self.time.as_secs_f64() {
             0.0 => 0.0,
-            t => self.moves_cnt as f64 / t,
+            t => metrics.mtm as f64 / t,
         };
         layout.push(
-            format!("{} moves / {:.2} mps", self.moves_cnt, mps)
-                .fg(Color::Gray),
+            format!("{} moves / {:.2} mps", metrics.mtm, mps).fg(Color::Gray),
+            Constraint::Min(0),
+        );
+        layout.push(
+            format!(
+                "{} stm / {} btm / {} etm",
+                metrics.stm, metrics.btm, metrics.etm
+            )
+            .fg(Color::Gray),
             Constraint::Min(0),
         );
     }
@@ -127,10 +258,8 @@ impl App {
     /// Gets the best time paragraph
     fn simple_stats_best(&self, best: &Stat) -> Paragraph {
         Paragraph::new(vec![
-            format!("{:.3}", best.time().as_secs_f64())
-                .fg(Color::Green)
-                .into(),
-            best.moves_cnt().to_string().fg(Color::DarkGreen).into(),
+            format_stat_secs(best).fg(Color::Green).into(),
+            best.metrics().mtm.to_string().fg(Color::DarkGreen).into(),
         ])
         .separator(" ")
     }
@@ -140,22 +269,41 @@ impl App {
         let cnt = self.board.height(&Vec2::new(0, 0)).saturating_sub(4);
         for stat in self.stats.solves().iter().take(cnt) {
             let p = Paragraph::new(vec![
-                format!("{:.3}", stat.time().as_secs_f64())
-                    .fg(Color::White)
-                    .into(),
-                stat.moves_cnt().to_string().fg(Color::Gray).into(),
+                format_stat_secs(stat).fg(Color::White).into(),
+                stat.metrics().mtm.to_string().fg(Color::Gray).into(),
             ])
             .separator(" ");
             layout.push(p, Constraint::Min(0));
         }
     }
 
+    /// Gets banner with the personal bests set by the last solve
+    fn render_new_pbs(&self) -> Layout {
+        let mut layout = Layout::horizontal().center();
+        let Some((pbs, _)) = &self.new_pbs else {
+            return layout;
+        };
+
+        let mut banner: Vec<Box<dyn Text>> =
+            vec!["New PB!".fg(Color::Yellow).modifier(Modifier::BOLD).into()];
+        for pb in pbs {
+            let pb = format!("{} {}", pb.record, pb.value);
+            banner.push(pb.fg(Color::Yellow).into());
+        }
+        layout
+            .push(Paragraph::new(banner).separator("  "), Constraint::Min(0));
+        layout
+    }
+
     /// Renders help with all the keybinds
     fn render_help() -> Paragraph {
         Paragraph::new(vec![
             "[Arrows]Move".fg(Color::Gray).into(),
             "[Shift+Arrows]Rotate".fg(Color::Gray).into(),
+            "[Ctrl+Shift+Arrows]Resize sel.".fg(Color::Gray).into(),
             "[Enter]Scramble".fg(Color::Gray).into(),
+            "[h]Hint".fg(Color::Gray).into(),
+            "[z|y]Undo|Redo".fg(Color::Gray).into(),
             "[Tab]Stats".fg(Color::Gray).into(),
             "[Esc|q]Quit".fg(Color::Gray).into(),
         ])
@@ -170,7 +318,9 @@ impl App {
     fn game_loop(&mut self) -> Result<(), Error> {
         self.state = State::Playing;
         self.time = Duration::from_secs(0);
-        self.moves_cnt = 1;
+        self.redo.clear();
+        self.undos = 0;
+        self.penalty = Duration::from_secs(0);
         self.render()?;
 
         if self.board.solved() {
@@ -183,10 +333,10 @@ impl App {
         let mut running = true;
         while running {
             if poll(Duration::from_millis(1))? {
-                self.time = start.elapsed();
+                self.time = start.elapsed() + self.penalty;
                 running = !self.event()?;
             } else if last.elapsed() >= Duration::from_secs_f64(0.001) {
-                self.time = start.elapsed();
+                self.time = start.elapsed() + self.penalty;
                 last = Instant::now();
                 self.render()?;
             }
@@ -209,35 +359,159 @@ impl App {
         mov(self);
         if event.modifiers.contains(KeyModifiers::SHIFT) {
             rot(self);
+            self.board.hint = None;
+            let c = c.to_ascii_uppercase();
             match self.state {
                 State::Scrambled => {
-                    self.moves = c.to_uppercase().to_string();
+                    self.start_solve(c);
                     self.game_loop()?
                 }
                 State::Playing => {
-                    self.moves_cnt += 1;
-                    self.moves.push(' ');
-                    self.moves.push(c.to_uppercase().next().unwrap_or(c))
+                    self.record(c);
+                    self.redo.clear();
                 }
                 _ => {}
             }
             return Ok(self.board.solved());
         } else if self.state == State::Playing {
-            self.moves.push_str(&format!(" {c}"));
+            self.record(c);
+            self.redo.clear();
         }
         Ok(false)
     }
 
+    /// Clears the previous solve and records the first move of new solve
+    fn start_solve(&mut self, c: char) {
+        self.time = Duration::from_secs(0);
+        self.penalty = Duration::from_secs(0);
+        self.moves.clear();
+        self.move_times.clear();
+        self.history.clear();
+        self.metrics = MetricsCounter::default();
+        // Recorded moves always start with single cell selected
+        for _ in 1..self.board.span.x {
+            self.record('>');
+        }
+        for _ in 1..self.board.span.y {
+            self.record('v');
+        }
+        self.record(c);
+    }
+
+    /// Resizes the selection, recording the change when playing
+    fn resize_selection<F>(&mut self, resize: F, c: char) -> bool
+    where
+        F: Fn(&mut Board) -> bool,
+    {
+        if resize(&mut self.board) && self.state == State::Playing {
+            self.record(c);
+            self.redo.clear();
+        }
+        false
+    }
+
+    /// Records given move to the moves and to the undo history
+    fn record(&mut self, c: char) {
+        self.history.push(c);
+        self.push_move(c);
+    }
+
+    /// Pushes given move to the moves string with its time offset
+    fn push_move(&mut self, c: char) {
+        if !self.moves.is_empty() {
+            self.moves.push(' ');
+        }
+        self.moves.push(c);
+
+        let offset = self.time.saturating_sub(self.penalty);
+        self.move_times.push(offset.as_millis() as u64);
+        self.metrics.push(c, &self.board);
+    }
+
+    /// Undoes moves up to the last rotation. Undone moves are recorded as
+    /// their inverse, so the replay of the solve stays faithful.
+    fn undo(&mut self) -> Result<bool, Error> {
+        if self.state != State::Playing || !self.config.allow_undo {
+            return Ok(false);
+        }
+
+        let mut group = Vec::new();
+        while let Some(c) = self.history.pop() {
+            self.board.apply_rev_move(c)?;
+            self.push_move(Board::inverse_move(c));
+            group.push(c);
+            if c.is_ascii_uppercase() {
+                break;
+            }
+        }
+
+        if !group.is_empty() {
+            group.reverse();
+            self.redo.push(group);
+            self.undos += 1;
+            let penalty = self.config.undo_penalty;
+            self.penalty +=
+                Duration::try_from_secs_f64(penalty).unwrap_or(Duration::ZERO);
+        }
+        Ok(self.board.solved())
+    }
+
+    /// Redoes the last undone moves
+    fn redo(&mut self) -> Result<bool, Error> {
+        if self.state != State::Playing {
+            return Ok(false);
+        }
+
+        for c in self.redo.pop().unwrap_or_default() {
+            self.board.apply_move(c)?;
+            self.record(c);
+        }
+        Ok(self.board.solved())
+    }
+
+    /// Shows the next move towards the solved board
+    fn hint(&mut self) -> Result<(), Error> {
+        if self.board.solved() {
+            return Ok(());
+        }
+
+        self.board.hint = solver::solve(&self.board, None)?.first().copied();
+        if self.state != State::Idle {
+            self.hints += 1;
+        }
+        Ok(())
+    }
+
     /// Saves stat
     fn save_stat(&mut self) -> Result<(), Error> {
         if self.state == State::Playing {
-            self.stats.add(Stat::new(
+            let metrics = Metrics::new(
+                &self.moves,
+                self.board.selected,
+                self.board.size,
+            )
+            .unwrap_or(*self.metrics.metrics());
+            let stat = Stat::new(
                 self.time,
-                self.moves_cnt,
+                metrics,
                 self.moves.clone(),
                 self.board.selected,
-            ));
-            self.stats.save(&self.board.size)?;
+                self.hints,
+            );
+            let stat = stat
+                .with_scramble(self.seed, self.scramble.clone())
+                .with_undos(self.undos)
+                .with_times(self.move_times.clone());
+            let date = stat.date();
+            let pbs = self.storage.add_solve(
+                &self.board.size,
+                &mut self.stats,
+                stat,
+            )?;
+            self.activity.add(date);
+            if !pbs.is_empty() {
+                self.new_pbs = Some((pbs, Instant::now()));
+            }
             self.state = State::Idle;
 
             if self.stat_state.borrow().selected > 0 {
@@ -249,3 +523,57 @@ impl App {
         Ok(())
     }
 }
+
+/// Formats given time in seconds, [`None`] is formatted as DNF
+fn format_secs(time: Option<Duration>) -> String {
+    time.map(|t| format!("{:.3}", t.as_secs_f64()))
+        .unwrap_or("DNF".to_string())
+}
+
+/// Formats time of the given stat in seconds including the penalty
+fn format_stat_secs(stat: &Stat) -> String {
+    match stat.penalty() {
+        Penalty::PlusTwo => format!("{}+", format_secs(stat.final_time())),
+        _ => format_secs(stat.final_time()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::remove_dir_all, path::Path};
+
+    use crate::{
+        config::Config, stats::storage::json::JsonStorage, test_util::test_dir,
+    };
+
+    use super::*;
+
+    /// Creates app with the stats saved in the test directory
+    fn app(dir: &Path, config: Config) -> App {
+        let storage = Box::new(JsonStorage::with_dir(dir.to_path_buf()));
+        App::with_storage(Vec2::new(3, 3), config, storage).unwrap()
+    }
+
+    /// Scrambles the board and starts the solve with given first move
+    fn start(app: &mut App, c: char) {
+        app.scramble();
+        app.start_solve(c);
+        app.state = State::Playing;
+    }
+
+    #[test]
+    fn metrics_reset_between_solves() {
+        let dir = test_dir("game-metrics");
+        let mut app = app(&dir, Config::default());
+        start(&mut app, 'U');
+        app.record('R');
+        app.save_stat().unwrap();
+        assert_eq!(app.stats.solves()[0].metrics().mtm, 2);
+
+        start(&mut app, 'D');
+        assert_eq!(app.metrics.metrics().mtm, 1);
+        app.save_stat().unwrap();
+        assert_eq!(app.stats.solves()[0].metrics().mtm, 1);
+        remove_dir_all(dir).unwrap();
+    }
+}
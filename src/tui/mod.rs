@@ -1,3 +1,4 @@
 pub mod game;
+pub mod overview;
 pub mod stats;
 pub mod widget;
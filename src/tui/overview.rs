@@ -0,0 +1,176 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use termint::{
+    enums::{Color, Modifier},
+    geometry::{Constraint, Vec2},
+    style::Style,
+    widgets::{Block, BorderType, Layout, Paragraph, StrSpanExtension},
+};
+
+use crate::{
+    app::{App, Screen},
+    board::board_struct::Board,
+    error::Error,
+    stats::{overview::SizeOverview, stat::format_duration},
+};
+
+use super::stats::StatsState;
+
+/// Widths of the overview columns
+const COLUMNS: [usize; 5] = [9, 9, 11, 11, 12];
+
+/// Overview state containing the summaries of all the sizes and the
+/// selected size
+#[derive(Debug, Default)]
+pub struct OverviewState {
+    pub sizes: Vec<SizeOverview>,
+    pub selected: usize,
+}
+
+//===========================================================================//
+//                         Public overview methods                           //
+//===========================================================================//
+impl App {
+    /// Renders the overview screen
+    pub fn render_overview(&mut self) -> Result<(), Error> {
+        let mut block = Block::vertical()
+            .title("Overview".fg(Color::White))
+            .border_type(BorderType::Thicker);
+        block.push(
+            Self::overview_row(
+                ["Size", "Solves", "Best", "ao5", "Last played"]
+                    .map(String::from),
+                Style::new().fg(Color::White).modifier(Modifier::BOLD),
+            ),
+            Constraint::Length(1),
+        );
+
+        if self.overview.sizes.is_empty() {
+            block.push("No stats yet...".fg(Color::Gray), Constraint::Min(0));
+        }
+        for (id, overview) in self.overview.sizes.iter().enumerate() {
+            let style = match id == self.overview.selected {
+                true => Style::new().fg(Color::Cyan),
+                false => Style::new().fg(Color::Gray),
+            };
+            let row = [
+                format!("{}x{}", overview.size.x, overview.size.y),
+                overview.solves.to_string(),
+                overview.best.map(format_duration).unwrap_or("-".into()),
+                overview.best_ao5.map(format_duration).unwrap_or("-".into()),
+                overview
+                    .last
+                    .map(|d| d.format("%d/%m/%Y").to_string())
+                    .unwrap_or("-".into()),
+            ];
+            block.push(Self::overview_row(row, style), Constraint::Length(1));
+        }
+
+        let mut hor_center = Layout::horizontal().center();
+        hor_center.push(block, Constraint::Min(0));
+
+        let mut ver_center = Layout::vertical().center();
+        ver_center.push(hor_center, Constraint::Min(0));
+
+        let mut layout = Layout::vertical();
+        layout.push(ver_center, Constraint::Fill(1));
+        layout.push(Self::render_overview_help(), Constraint::Min(0));
+        self.term.render(layout)?;
+        Ok(())
+    }
+
+    /// Handles key events for the overview screen
+    pub fn listen_overview(&mut self, event: KeyEvent) -> Result<(), Error> {
+        match event.code {
+            KeyCode::Up => {
+                self.overview.selected =
+                    self.overview.selected.saturating_sub(1)
+            }
+            KeyCode::Down => {
+                if self.overview.selected + 1 < self.overview.sizes.len() {
+                    self.overview.selected += 1;
+                }
+            }
+            KeyCode::Enter => {
+                if let Some(overview) =
+                    self.overview.sizes.get(self.overview.selected)
+                {
+                    self.open_stats(overview.size)?;
+                }
+                self.screen = Screen::Stats;
+            }
+            KeyCode::Char('o') => self.screen = Screen::Stats,
+            KeyCode::Tab => {
+                self.open_stats(self.board.size)?;
+                self.screen = Screen::Game;
+            }
+            KeyCode::Char('c')
+                if event.modifiers.contains(KeyModifiers::CONTROL) =>
+            {
+                return Err(Error::Exit);
+            }
+            KeyCode::Esc | KeyCode::Char('q') => return Err(Error::Exit),
+            _ => return Ok(()),
+        }
+        self.render()
+    }
+
+    /// Loads the summaries of all the sizes and shows the overview screen
+    /// with the size of the shown stats selected
+    pub fn open_overview(&mut self) -> Result<(), Error> {
+        let sizes = SizeOverview::load_all(&*self.storage)?;
+        self.overview.selected = sizes
+            .iter()
+            .position(|o| o.size == self.stats_size)
+            .unwrap_or(0);
+        self.overview.sizes = sizes;
+        self.screen = Screen::Overview;
+        Ok(())
+    }
+
+    /// Loads the stats of board with given size to be shown on the stats
+    /// screen, when they aren't loaded already
+    pub fn open_stats(&mut self, size: Vec2) -> Result<(), Error> {
+        if size == self.stats_size {
+            return Ok(());
+        }
+
+        self.stats = self.storage.load(&size)?;
+        self.stats_size = size;
+        self.stat_board = Board::new(size);
+        {
+            let mut state = self.stat_state.borrow_mut();
+            *state = StatsState {
+                height: state.height,
+                panel: state.panel,
+                ..Default::default()
+            };
+        }
+        self.load_stat_board()
+    }
+}
+
+//===========================================================================//
+//                        Private overview methods                           //
+//===========================================================================//
+impl App {
+    /// Creates row of the overview with given values of the columns
+    fn overview_row(row: [String; COLUMNS.len()], style: Style) -> Layout {
+        let mut layout = Layout::horizontal().padding((0, 1));
+        for (val, width) in row.into_iter().zip(COLUMNS) {
+            layout.push(val.style(style), Constraint::Length(width));
+        }
+        layout
+    }
+
+    /// Renders help with all the keybinds
+    fn render_overview_help() -> Paragraph {
+        Paragraph::new(vec![
+            "[Up/Down]Change sel.".fg(Color::Gray).into(),
+            "[Enter]Open stats".fg(Color::Gray).into(),
+            "[o]Back".fg(Color::Gray).into(),
+            "[Tab]Game".fg(Color::Gray).into(),
+            "[Esc|q]Quit".fg(Color::Gray).into(),
+        ])
+        .separator("  ")
+    }
+}
@@ -1,4 +1,12 @@
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::{
+    cmp::min,
+    time::{Duration, Instant},
+};
+
+use crossterm::{
+    event::{KeyCode, KeyEvent, KeyModifiers},
+    terminal,
+};
 use termint::{
     enums::Color,
     geometry::Constraint,
@@ -11,16 +19,85 @@ use termint::{
 use crate::{
     app::{App, Screen},
     error::Error,
+    stats::{
+        activity,
+        average::Average,
+        distribution::Distribution,
+        session::SessionChange,
+        sort::SortKey,
+        stat::{format_duration, format_result, Penalty, Stat},
+    },
 };
 
-use super::widget::table::Table;
+use super::widget::{
+    calendar::Calendar,
+    graph::{Graph, GraphAxis, Series},
+    histogram::Histogram,
+    table::{Row, Table},
+};
+
+/// Replay speeds available on the stats screen
+const SPEEDS: [f64; 3] = [0.5, 1., 2.];
+
+/// Height of the panel below the solves table
+const PANEL_HEIGHT: usize = 12;
 
-/// Stats state containing list offset, selected stat and move offset
-#[derive(Debug, Default)]
+/// Number of the histogram buckets when the bucket width isn't set
+const HISTOGRAM_BUCKETS: usize = 20;
+
+/// Panel shown below the solves table
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Panel {
+    #[default]
+    None,
+    /// Graph of the solve times plotted over given axis
+    Graph(GraphAxis),
+    /// Histogram of the solve times
+    Histogram,
+    /// Calendar of the solves on each day across all the sizes
+    Activity,
+    /// Timeline of the personal bests
+    Records,
+}
+
+/// Stats state containing list offset, selected stat, move offset, the
+/// replay playback state and whether deletion waits for confirmation
+#[derive(Debug)]
 pub struct StatsState {
     pub offset: usize,
+    /// Index of the selected solve in the session solves, so it stays the
+    /// same when the table is sorted
     pub selected: usize,
     pub move_offset: usize,
+    pub playing: bool,
+    pub speed: usize,
+    pub replay_time: Duration,
+    pub last_tick: Option<Instant>,
+    pub confirm_delete: bool,
+    /// Number of the visible table rows, zero before the first render
+    pub height: usize,
+    /// Panel shown below the solves table
+    pub panel: Panel,
+    /// Key the solves table is sorted by
+    pub sort: SortKey,
+}
+
+impl Default for StatsState {
+    fn default() -> Self {
+        Self {
+            offset: 0,
+            selected: 0,
+            move_offset: 0,
+            playing: false,
+            speed: 1,
+            replay_time: Duration::from_secs(0),
+            last_tick: None,
+            confirm_delete: false,
+            height: 0,
+            panel: Panel::None,
+            sort: SortKey::Date,
+        }
+    }
 }
 
 //===========================================================================//
@@ -29,14 +106,44 @@ pub struct StatsState {
 impl App {
     /// Renders the game screen
     pub fn render_stats(&mut self) -> Result<(), Error> {
-        let mut layout = Block::horizontal().border_type(BorderType::Thicker);
+        let session = self.stats.session().name();
+        let mut layout = Block::horizontal()
+            .title(
+                format!(
+                    "{}x{} session: {session}",
+                    self.stats_size.x, self.stats_size.y
+                )
+                .fg(Color::White),
+            )
+            .border_type(BorderType::Thicker);
+        let selected = self.selected_row()?;
+        let (len, rows) = self.table_rows()?;
         let mut list = Layout::vertical().padding((0, 0, 0, 1));
         list.push(
-            Table::new(self.stats.clone(), self.stat_state.clone()),
+            Table::new(rows, len, selected, self.stat_state.clone()),
             Constraint::Fill(1),
         );
+        let panel = self.stat_state.borrow().panel;
+        match panel {
+            Panel::None => {}
+            Panel::Graph(axis) => list.push(
+                self.solves_graph(axis),
+                Constraint::Length(PANEL_HEIGHT),
+            ),
+            Panel::Histogram => list.push(
+                self.solves_histogram(),
+                Constraint::Length(PANEL_HEIGHT),
+            ),
+            Panel::Activity => list.push(
+                Calendar::new(self.activity.clone(), activity::today()),
+                Constraint::Length(PANEL_HEIGHT),
+            ),
+            Panel::Records => {
+                list.push(self.pb_timeline(), Constraint::Length(PANEL_HEIGHT))
+            }
+        }
         layout.push(list, Constraint::Min(0));
-        self.render_stat(&mut layout);
+        self.render_stat(&mut layout)?;
 
         let mut hor_center = Layout::horizontal().center();
         hor_center.push(layout, Constraint::Min(0));
@@ -45,25 +152,73 @@ impl App {
         ver_center.push(Spacer::new(), Constraint::Fill(1));
         ver_center.push(hor_center, Constraint::Percent(75));
         ver_center.push(Spacer::new(), Constraint::Fill(1));
-        ver_center.push(Self::render_stats_help(), Constraint::Min(0));
+        match self.stat_state.borrow().confirm_delete {
+            true => ver_center
+                .push(Self::render_delete_prompt(), Constraint::Min(0)),
+            false => {
+                ver_center.push(Self::render_stats_help(), Constraint::Min(0))
+            }
+        }
         self.term.render(ver_center)?;
         Ok(())
     }
 
     /// Handles key events for the game screen
     pub fn listen_stats(&mut self, event: KeyEvent) -> Result<(), Error> {
+        if self.stat_state.borrow().confirm_delete {
+            return self.listen_delete(event);
+        }
+
         match event.code {
             KeyCode::Up => {
-                self.select_prev();
+                self.select_row(-1)?;
                 self.load_stat_board()?;
             }
             KeyCode::Down => {
-                self.select_next();
+                self.select_row(1)?;
                 self.load_stat_board()?;
             }
-            KeyCode::Left => self.prev_move()?,
-            KeyCode::Right => self.next_move()?,
-            KeyCode::Tab => self.screen = Screen::Game,
+            KeyCode::Left => {
+                self.pause_replay();
+                self.prev_move()?
+            }
+            KeyCode::Right => {
+                self.pause_replay();
+                self.next_move()?
+            }
+            KeyCode::Char(' ') => self.toggle_replay()?,
+            KeyCode::Char('+') => self.change_speed(1),
+            KeyCode::Char('-') => self.change_speed(-1),
+            KeyCode::Char('[') => {
+                let name = self.stats.cycled_session(-1).to_string();
+                self.change_session(SessionChange::Switch(name))?
+            }
+            KeyCode::Char(']') => {
+                let name = self.stats.cycled_session(1).to_string();
+                self.change_session(SessionChange::Switch(name))?
+            }
+            KeyCode::Char('n') => {
+                let name = self.stats.next_session_name();
+                self.change_session(SessionChange::New(name))?
+            }
+            KeyCode::Char('g') => self.toggle_graph(),
+            KeyCode::Char('h') => self.toggle_panel(Panel::Histogram),
+            KeyCode::Char('a') => self.toggle_panel(Panel::Activity),
+            KeyCode::Char('b') => self.toggle_panel(Panel::Records),
+            KeyCode::Char('s') => self.cycle_sort()?,
+            KeyCode::Char('d') => self.toggle_penalty(Penalty::Dnf)?,
+            KeyCode::Char('p') => self.toggle_penalty(Penalty::PlusTwo)?,
+            KeyCode::Delete | KeyCode::Char('x')
+                if !self.stats.solves().is_empty() =>
+            {
+                self.pause_replay();
+                self.stat_state.borrow_mut().confirm_delete = true;
+            }
+            KeyCode::Char('o') => self.open_overview()?,
+            KeyCode::Tab => {
+                self.open_stats(self.board.size)?;
+                self.screen = Screen::Game;
+            }
             KeyCode::Char('c')
                 if event.modifiers.contains(KeyModifiers::CONTROL) =>
             {
@@ -75,6 +230,50 @@ impl App {
         self.render()
     }
 
+    /// Gets timeout of the events polling, which is shorter when replay is
+    /// playing, so it is smooth
+    pub fn poll_timeout(&self) -> Duration {
+        match self.screen == Screen::Stats && self.stat_state.borrow().playing
+        {
+            true => Duration::from_millis(10),
+            false => Duration::from_millis(100),
+        }
+    }
+
+    /// Advances the replay of the selected solve when it's playing
+    pub fn replay_tick(&mut self) -> Result<(), Error> {
+        {
+            let mut state = self.stat_state.borrow_mut();
+            if !state.playing {
+                return Ok(());
+            }
+
+            let now = Instant::now();
+            let last = state.last_tick.replace(now).unwrap_or(now);
+            let speed = SPEEDS[state.speed];
+            state.replay_time += (now - last).mul_f64(speed);
+        }
+
+        let mut moved = false;
+        while let Some(time) = self.next_move_time() {
+            if time > self.stat_state.borrow().replay_time {
+                break;
+            }
+            self.next_move()?;
+            moved = true;
+        }
+
+        if self.next_move_time().is_none() {
+            self.pause_replay();
+            moved = true;
+        }
+
+        match moved {
+            true => self.render(),
+            false => Ok(()),
+        }
+    }
+
     /// Loads the board of currently selected
     pub fn load_stat_board(&mut self) -> Result<(), Error> {
         let state = self.stat_state.borrow();
@@ -89,10 +288,10 @@ impl App {
 //                          Private stats methods                            //
 //===========================================================================//
 impl App {
-    fn render_stat(&self, layout: &mut Block<Layout>) {
+    fn render_stat(&self, layout: &mut Block<Layout>) -> Result<(), Error> {
         let state = self.stat_state.borrow();
         let Some(stat) = self.stats.solves().get(state.selected) else {
-            return;
+            return Ok(());
         };
 
         let mut slayout = Layout::vertical().padding((0, 1));
@@ -105,7 +304,12 @@ impl App {
         Self::render_item(
             &mut slayout,
             "Time:",
-            &stat.format_time(),
+            &match stat.penalty() {
+                Penalty::Dnf => {
+                    format!("DNF ({})", format_duration(stat.time()))
+                }
+                _ => stat.format_time(),
+            },
             Style::new().fg(Color::Cyan),
         );
         Self::render_item(
@@ -114,12 +318,76 @@ impl App {
             &stat.date().format("%d/%m/%Y %H:%M:%S").to_string(),
             Style::new().fg(Color::DarkYellow),
         );
+        let metrics = stat.metrics();
         Self::render_item(
             &mut slayout,
             "Moves:",
-            &stat.moves_cnt().to_string(),
+            &format!(
+                "{} mtm / {} stm / {} btm",
+                metrics.mtm, metrics.stm, metrics.btm
+            ),
             Style::new().fg(Color::Red),
         );
+        Self::render_item(
+            &mut slayout,
+            "Keys:",
+            &format!("{} etm / {} cursor", metrics.etm, metrics.cursor),
+            Style::new().fg(Color::DarkRed),
+        );
+        Self::render_item(
+            &mut slayout,
+            "Replay:",
+            &format!(
+                "{} {}x",
+                if state.playing { "▶" } else { "⏸" },
+                SPEEDS[state.speed]
+            ),
+            Style::new().fg(Color::Green),
+        );
+        if let Some(seed) = stat.seed() {
+            Self::render_item(
+                &mut slayout,
+                "Seed:",
+                &seed.to_string(),
+                Style::new().fg(Color::Blue),
+            );
+        }
+        if let Some(scramble) = stat.scramble() {
+            Self::render_item(
+                &mut slayout,
+                "State:",
+                scramble,
+                Style::new().fg(Color::Gray),
+            );
+        }
+        if stat.undos() > 0 {
+            Self::render_item(
+                &mut slayout,
+                "Undos:",
+                &stat.undos().to_string(),
+                Style::new().fg(Color::DarkYellow),
+            );
+        }
+        if stat.hints() > 0 {
+            Self::render_item(
+                &mut slayout,
+                "Hints:",
+                &stat.hints().to_string(),
+                Style::new().fg(Color::DarkYellow),
+            );
+        }
+        Self::render_item(
+            &mut slayout,
+            "PBs:",
+            &self.all_time_bests()?,
+            Style::new().fg(Color::Green),
+        );
+        Self::render_item(
+            &mut slayout,
+            "Best:",
+            &self.move_bests()?,
+            Style::new().fg(Color::DarkGreen),
+        );
         slayout.push(Spacer::new(), Constraint::Fill(1));
 
         let mut wrapper = Layout::horizontal().center();
@@ -128,6 +396,7 @@ impl App {
         slayout.push(wrapper, Constraint::Min(0));
         slayout.push(Spacer::new(), Constraint::Fill(1));
         layout.push(slayout, Constraint::Min(0));
+        Ok(())
     }
 
     fn render_item(
@@ -142,37 +411,433 @@ impl App {
         layout.push(wrapper, Constraint::Length(1));
     }
 
+    /// Gets all-time best single and averages across all the sessions
+    fn all_time_bests(&self) -> Result<String, Error> {
+        let size = &self.stats_size;
+        let single = self.storage.best(size)?.and_then(|s| s.final_time());
+        let mut bests = vec![format_result(single)];
+        for avg in [Average::Ao5, Average::Ao12] {
+            if let Some(best) = self.storage.best_average(size, avg)? {
+                bests.push(format!("{avg} {}", format_duration(best)));
+            }
+        }
+        Ok(bests.join(" / "))
+    }
+
+    /// Gets all-time fewest moves and highest moves per second
+    fn move_bests(&self) -> Result<String, Error> {
+        let size = &self.stats_size;
+        let moves = self.storage.fewest_moves(size)?.map(|s| s.metrics().mtm);
+        let mps = self.storage.highest_mps(size)?.map(|s| s.mps());
+        Ok(match (moves, mps) {
+            (Some(moves), Some(mps)) => {
+                format!("{moves} moves / {mps:.2} mps")
+            }
+            _ => "-".into(),
+        })
+    }
+
+    /// Creates graph of the active session solve times and their rolling
+    /// averages, from the oldest solve
+    fn solves_graph(&self, axis: GraphAxis) -> Graph {
+        let solves = self.stats.solves();
+        let x = |id: usize| match axis {
+            GraphAxis::Index => (solves.len() - id) as f64,
+            GraphAxis::Date => solves[id].date().timestamp() as f64,
+        };
+        let points = |time: &dyn Fn(usize) -> Option<Duration>| {
+            (0..solves.len())
+                .rev()
+                .map(|id| (x(id), time(id)))
+                .collect()
+        };
+
+        let mut series = vec![Series::new(
+            "time",
+            Color::Gray,
+            points(&|id| solves[id].final_time()),
+        )];
+        for (avg, color) in
+            [(Average::Ao5, Color::Cyan), (Average::Ao12, Color::Yellow)]
+        {
+            let avgs = points(&|id| self.stats.average(avg, id).flatten());
+            series.push(Series::new(avg.to_string(), color, avgs).line());
+        }
+
+        let graph = Graph::new(series);
+        match (axis, solves.last(), solves.first()) {
+            (GraphAxis::Index, _, _) => {
+                graph.labels("1", solves.len().to_string())
+            }
+            (GraphAxis::Date, Some(first), Some(last)) => graph.labels(
+                first.date().format("%d/%m/%Y").to_string(),
+                last.date().format("%d/%m/%Y").to_string(),
+            ),
+            _ => graph,
+        }
+    }
+
+    /// Creates histogram of the active session solve times with the
+    /// median and the quartiles marked
+    fn solves_histogram(&self) -> Histogram {
+        let dist = Distribution::new(self.stats.solves());
+        let bucket = match self.config.histogram_bucket {
+            b if b > 0. => Duration::try_from_secs_f64(b).ok(),
+            _ => None,
+        };
+        let bucket =
+            bucket.unwrap_or_else(|| dist.bucket_width(HISTOGRAM_BUCKETS));
+
+        let markers = [
+            ("p25", dist.percentile(25.), Color::Cyan),
+            ("median", dist.median(), Color::Yellow),
+            ("p75", dist.percentile(75.), Color::Cyan),
+            ("p90", dist.percentile(90.), Color::Magenta),
+        ];
+        let mut hist = Histogram::new(dist, bucket);
+        for (name, time, color) in markers {
+            if let Some(time) = time {
+                hist = hist.marker(name, time, color);
+            }
+        }
+        hist
+    }
+
+    /// Cycles the solves graph between hidden, plotted over the solve
+    /// number and plotted over the solve date
+    fn toggle_graph(&mut self) {
+        let mut state = self.stat_state.borrow_mut();
+        state.panel = match state.panel {
+            Panel::Graph(GraphAxis::Index) => Panel::Graph(GraphAxis::Date),
+            Panel::Graph(GraphAxis::Date) => Panel::None,
+            _ => Panel::Graph(GraphAxis::Index),
+        };
+    }
+
+    /// Creates timeline of the personal bests, the newest first
+    fn pb_timeline(&self) -> Layout {
+        let history = self.stats.pb_history();
+        let mut layout = Layout::vertical();
+        layout.push(
+            format!("PB history ({} records)", history.len()).fg(Color::White),
+            Constraint::Length(1),
+        );
+
+        for pb in history.iter().rev().take(PANEL_HEIGHT - 1) {
+            let mut row = Layout::horizontal();
+            let date = pb.date.format("%d/%m/%Y").to_string();
+            row.push(date.fg(Color::Gray), Constraint::Length(12));
+            row.push(
+                pb.record.to_string().fg(Color::White),
+                Constraint::Length(8),
+            );
+            row.push(
+                pb.value.to_string().fg(Color::Green),
+                Constraint::Length(10),
+            );
+            let improvement = pb.improvement().unwrap_or_default();
+            row.push(improvement.fg(Color::DarkGreen), Constraint::Length(25));
+            layout.push(row, Constraint::Length(1));
+        }
+        layout
+    }
+
+    /// Shows given panel or hides it when it's already shown
+    fn toggle_panel(&mut self, panel: Panel) {
+        let mut state = self.stat_state.borrow_mut();
+        state.panel = match state.panel == panel {
+            true => Panel::None,
+            false => panel,
+        };
+    }
+
+    /// Sorts the table by the next sort key and scrolls it to keep the
+    /// selected solve visible
+    fn cycle_sort(&mut self) -> Result<(), Error> {
+        let sort = self.stat_state.borrow().sort.next();
+        self.stat_state.borrow_mut().sort = sort;
+
+        let selected = self.selected_row()?;
+        let len = self
+            .storage
+            .count(&self.stats_size, self.stats.session().name())?;
+        let mut state = self.stat_state.borrow_mut();
+        state.offset = selected
+            .saturating_sub(state.height / 2)
+            .min(len.saturating_sub(state.height));
+        Ok(())
+    }
+
+    /// Gets position of the selected solve in the sorted table
+    fn selected_row(&self) -> Result<usize, Error> {
+        let state = self.stat_state.borrow();
+        let Some(stat) = self.stats.solves().get(state.selected) else {
+            return Ok(0);
+        };
+        let session = self.stats.session().name();
+        let row = self.storage.position(
+            &self.stats_size,
+            session,
+            state.sort,
+            stat.date(),
+        )?;
+        Ok(row.unwrap_or(0))
+    }
+
+    /// Gets index of the solve in the given row of the sorted table in the
+    /// session solves
+    fn row_solve(&self, row: usize) -> Result<Option<usize>, Error> {
+        let sort = self.stat_state.borrow().sort;
+        let session = self.stats.session().name();
+        let page =
+            self.storage.page(&self.stats_size, session, sort, row, 1)?;
+        Ok(page.first().and_then(|solve| self.solve_index(solve)))
+    }
+
+    /// Gets index of the solve from the storage page in the loaded session
+    /// solves. The index is checked, because other instance could add
+    /// solves since the session was loaded.
+    fn solve_index(&self, (id, stat): &(usize, Stat)) -> Option<usize> {
+        match self.stats.solves().get(*id) {
+            Some(solve) if solve.date() == stat.date() => Some(*id),
+            _ => self.stats.position(stat.date()),
+        }
+    }
+
+    /// Scrolls the table to keep the selected row visible with a few rows
+    /// around it
+    fn scroll_table(&self) -> Result<(), Error> {
+        let selected = self.selected_row()?;
+        let len = self
+            .storage
+            .count(&self.stats_size, self.stats.session().name())?;
+
+        let mut state = self.stat_state.borrow_mut();
+        let margin = min(3, state.height.saturating_sub(1) / 2);
+        state.offset = state
+            .offset
+            .min(selected.saturating_sub(margin))
+            .max((selected + margin + 1).saturating_sub(state.height))
+            .min(len.saturating_sub(state.height));
+        Ok(())
+    }
+
+    /// Gets the total number of solves and the visible rows of the table
+    fn table_rows(&self) -> Result<(usize, Vec<Row>), Error> {
+        let size = &self.stats_size;
+        let session = self.stats.session().name();
+        let state = self.stat_state.borrow();
+        let len = self.storage.count(size, session)?;
+        let height = match state.height {
+            0 => terminal::size()?.1 as usize,
+            height => height,
+        };
+
+        // Following solves are needed to compute averages of the last rows
+        let sort = state.sort;
+        let page_len = match sort {
+            SortKey::Date => height + Average::Ao100.count() - 1,
+            _ => height,
+        };
+        let page =
+            self.storage
+                .page(size, session, sort, state.offset, page_len)?;
+        let rows = page
+            .iter()
+            .take(height)
+            .enumerate()
+            .map(|(row, solve)| {
+                Self::table_row(&solve.1, |avg| match sort {
+                    SortKey::Date => avg.compute(
+                        page[row..].iter().map(|(_, s)| s.final_time()),
+                    ),
+                    // Sorted rows don't follow each other, so the averages
+                    // are computed from the loaded session solves
+                    _ => self
+                        .solve_index(solve)
+                        .and_then(|id| self.stats.average(avg, id)),
+                })
+            })
+            .collect();
+        Ok((len, rows))
+    }
+
+    /// Gets the table row of given solve with the averages ending with it
+    fn table_row<F>(stat: &Stat, avg: F) -> Row
+    where
+        F: Fn(Average) -> Option<Option<Duration>>,
+    {
+        let [mo3, ao5, ao12, ao50, ao100] = Average::ALL
+            .map(|a| avg(a).map(format_result).unwrap_or("-".into()));
+        [
+            stat.date().format("%d/%m/%Y").to_string(),
+            stat.format_time(),
+            stat.metrics().mtm.to_string(),
+            format!("{:.2}", stat.mps()),
+            mo3,
+            ao5,
+            ao12,
+            ao50,
+            ao100,
+        ]
+    }
+
     /// Renders help with all the keybinds
-    fn render_stats_help() -> Paragraph {
+    fn render_stats_help() -> Layout {
+        let mut help = Layout::vertical();
+        help.push(
+            Paragraph::new(vec![
+                "[Up/Down]Change sel.".fg(Color::Gray).into(),
+                "[Left/Right]Replay solve".fg(Color::Gray).into(),
+                "[Space]Play/Pause".fg(Color::Gray).into(),
+                "[+/-]Speed".fg(Color::Gray).into(),
+                "[g]Graph".fg(Color::Gray).into(),
+                "[h]Histogram".fg(Color::Gray).into(),
+                "[a]Activity".fg(Color::Gray).into(),
+                "[b]PBs".fg(Color::Gray).into(),
+            ])
+            .separator("  "),
+            Constraint::Length(1),
+        );
+        help.push(
+            Paragraph::new(vec![
+                "[[|]]Session".fg(Color::Gray).into(),
+                "[n]New session".fg(Color::Gray).into(),
+                "[s]Sort".fg(Color::Gray).into(),
+                "[o]Overview".fg(Color::Gray).into(),
+                "[d|p]DNF|+2".fg(Color::Gray).into(),
+                "[x|Del]Delete".fg(Color::Gray).into(),
+                "[Tab]Game".fg(Color::Gray).into(),
+                "[Esc|q]Quit".fg(Color::Gray).into(),
+            ])
+            .separator("  "),
+            Constraint::Length(1),
+        );
+        help
+    }
+
+    /// Renders prompt confirming deletion of the selected solve
+    fn render_delete_prompt() -> Paragraph {
         Paragraph::new(vec![
-            "[Up/Down]Change sel.".fg(Color::Gray).into(),
-            "[Left/Right]Replay solve".fg(Color::Gray).into(),
-            "[Tab]Game".fg(Color::Gray).into(),
-            "[Esc|q]Quit".fg(Color::Gray).into(),
+            "Delete selected solve?".fg(Color::Red).into(),
+            "[y]Yes".fg(Color::Gray).into(),
+            "[Any]No".fg(Color::Gray).into(),
         ])
         .separator("  ")
     }
 
-    fn select_next(&mut self) {
-        let mut state = self.stat_state.borrow_mut();
-        state.move_offset = 0;
+    /// Handles key events when deletion waits for confirmation
+    fn listen_delete(&mut self, event: KeyEvent) -> Result<(), Error> {
+        self.stat_state.borrow_mut().confirm_delete = false;
+        match event.code {
+            KeyCode::Char('c')
+                if event.modifiers.contains(KeyModifiers::CONTROL) =>
+            {
+                return Err(Error::Exit);
+            }
+            KeyCode::Char('y') => self.delete_selected()?,
+            _ => {}
+        }
+        self.render()
+    }
 
-        if state.selected + 1 < self.stats.solves().len() {
-            state.selected += 1;
+    /// Deletes the selected solve and saves the stats
+    fn delete_selected(&mut self) -> Result<(), Error> {
+        let selected = self.stat_state.borrow().selected;
+        let Some(date) = self.stats.solves().get(selected).map(|s| s.date())
+        else {
+            return Ok(());
+        };
+        let removed = self.storage.remove_solve(
+            &self.stats_size,
+            &mut self.stats,
+            date,
+        )?;
+        if let Some(stat) = removed {
+            self.activity.remove(stat.date());
         }
+
+        {
+            let mut state = self.stat_state.borrow_mut();
+            state.move_offset = 0;
+            let last = self.stats.solves().len().saturating_sub(1);
+            state.selected = state.selected.min(last);
+        }
+        self.scroll_table()?;
+        self.load_stat_board()
     }
 
-    /// Sets selected mode to the previous value if previous exists
-    fn select_prev(&mut self) {
-        let mut state = self.stat_state.borrow_mut();
-        state.move_offset = 0;
-        state.selected = state.selected.saturating_sub(1);
+    /// Applies given change of the sessions, saves it and resets the
+    /// selection
+    fn change_session(&mut self, change: SessionChange) -> Result<(), Error> {
+        self.storage.change_session(
+            &self.stats_size,
+            &mut self.stats,
+            &change,
+        )?;
+
+        self.pause_replay();
+        let first = self.row_solve(0)?.unwrap_or(0);
+        {
+            let mut state = self.stat_state.borrow_mut();
+            state.offset = 0;
+            state.selected = first;
+            state.move_offset = 0;
+        }
+        self.load_stat_board()
+    }
+
+    /// Toggles given penalty of the selected solve and saves the stats
+    fn toggle_penalty(&mut self, penalty: Penalty) -> Result<(), Error> {
+        let selected = self.stat_state.borrow().selected;
+        let Some(stat) = self.stats.solves().get(selected) else {
+            return Ok(());
+        };
+
+        let penalty = match stat.penalty() == penalty {
+            true => Penalty::None,
+            false => penalty,
+        };
+        let date = stat.date();
+        self.storage.set_penalty(
+            &self.stats_size,
+            &mut self.stats,
+            date,
+            penalty,
+        )?;
+
+        // Other instances could add solves, so the selection has to follow
+        self.stat_state.borrow_mut().selected =
+            self.stats.position(date).unwrap_or(selected);
+        self.scroll_table()
+    }
+
+    /// Selects the solve given number of table rows after the selected row,
+    /// when it exists
+    fn select_row(&mut self, step: isize) -> Result<(), Error> {
+        let row = self.selected_row()?.checked_add_signed(step);
+        let solve = match row {
+            Some(row) => self.row_solve(row)?,
+            None => None,
+        };
+
+        {
+            let mut state = self.stat_state.borrow_mut();
+            state.move_offset = 0;
+            state.playing = false;
+            if let Some(id) = solve {
+                state.selected = id;
+            }
+        }
+        self.scroll_table()
     }
 
     /// Applies next move to the current solve preview
     fn next_move(&mut self) -> Result<(), Error> {
         let mut state = self.stat_state.borrow_mut();
-        let stat = &self.stats[state.selected];
+        let Some(stat) = self.stats.solves().get(state.selected) else {
+            return Ok(());
+        };
         if state.move_offset >= stat.moves().len() {
             return Ok(());
         }
@@ -192,7 +857,9 @@ impl App {
     /// Applies inverse prev move to the current solve preview
     fn prev_move(&mut self) -> Result<(), Error> {
         let mut state = self.stat_state.borrow_mut();
-        let stat = &self.stats[state.selected];
+        let Some(stat) = self.stats.solves().get(state.selected) else {
+            return Ok(());
+        };
         if state.move_offset == 0 {
             return Ok(());
         }
@@ -208,4 +875,122 @@ impl App {
         }
         Ok(())
     }
+
+    /// Starts or pauses the replay. When the replay is at the end, it starts
+    /// from the beginning.
+    fn toggle_replay(&mut self) -> Result<(), Error> {
+        if self.stat_state.borrow().playing {
+            self.pause_replay();
+            return Ok(());
+        }
+
+        if self.next_move_time().is_none() {
+            self.stat_state.borrow_mut().move_offset = 0;
+            self.load_stat_board()?;
+        }
+
+        let mut state = self.stat_state.borrow_mut();
+        let Some(stat) = self.stats.solves().get(state.selected) else {
+            return Ok(());
+        };
+        state.replay_time =
+            match Self::count_moves(&stat.moves()[..state.move_offset]) {
+                0 => Duration::from_secs(0),
+                n => stat.move_time(n - 1),
+            };
+        state.playing = true;
+        state.last_tick = Some(Instant::now());
+        Ok(())
+    }
+
+    /// Pauses the replay
+    fn pause_replay(&mut self) {
+        let mut state = self.stat_state.borrow_mut();
+        state.playing = false;
+        state.last_tick = None;
+    }
+
+    /// Changes the replay speed by given step
+    fn change_speed(&mut self, step: isize) {
+        let mut state = self.stat_state.borrow_mut();
+        state.speed = state
+            .speed
+            .saturating_add_signed(step)
+            .min(SPEEDS.len() - 1);
+    }
+
+    /// Gets time offset of the next move in the replay, [`None`] when the
+    /// replay is at the end
+    fn next_move_time(&self) -> Option<Duration> {
+        let state = self.stat_state.borrow();
+        let stat = self.stats.solves().get(state.selected)?;
+        let applied = Self::count_moves(&stat.moves()[..state.move_offset]);
+        match applied < Self::count_moves(stat.moves()) {
+            true => Some(stat.move_time(applied)),
+            false => None,
+        }
+    }
+
+    /// Gets number of moves in the given moves string
+    fn count_moves(moves: &str) -> usize {
+        moves.chars().filter(|c| !c.is_whitespace()).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::remove_dir_all;
+
+    use termint::geometry::Vec2;
+
+    use crate::{
+        config::Config,
+        stats::storage::json::JsonStorage,
+        test_util::{date, solve, test_dir},
+    };
+
+    use super::*;
+
+    #[test]
+    fn empty_session_replay() {
+        let dir = test_dir("stats-empty");
+        let storage = Box::new(JsonStorage::with_dir(dir.clone()));
+        let size = Vec2::new(3, 3);
+        let mut app =
+            App::with_storage(size, Config::default(), storage).unwrap();
+
+        app.next_move().unwrap();
+        app.prev_move().unwrap();
+        assert_eq!(app.stat_state.borrow().move_offset, 0);
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn selection_scrolls_table() {
+        let dir = test_dir("stats-scroll");
+        let storage = Box::new(JsonStorage::with_dir(dir.clone()));
+        let size = Vec2::new(3, 3);
+        let mut app =
+            App::with_storage(size, Config::default(), storage).unwrap();
+        for i in 0..20 {
+            let stat = solve(Duration::from_secs(i)).with_date(date(i as i64));
+            app.storage.add_solve(&size, &mut app.stats, stat).unwrap();
+        }
+        app.stat_state.borrow_mut().height = 10;
+
+        for step in [1, 1, 1, 1, 1, 1, 1, 1, -1, -1, -1, -1, -1, -1] {
+            app.select_row(step).unwrap();
+            let row = app.selected_row().unwrap();
+            let offset = app.stat_state.borrow().offset;
+            assert!(row >= offset && row < offset + 10);
+        }
+        assert_eq!(app.stat_state.borrow().offset, 0);
+
+        for _ in 0..30 {
+            app.select_row(1).unwrap();
+        }
+        assert_eq!(app.selected_row().unwrap(), 19);
+        assert_eq!(app.stat_state.borrow().offset, 10);
+        remove_dir_all(dir).unwrap();
+    }
 }
@@ -0,0 +1,130 @@
+use chrono::{Datelike, Duration, NaiveDate};
+use termint::{
+    buffer::Buffer,
+    enums::Color,
+    geometry::Vec2,
+    style::Style,
+    widgets::{Element, Widget},
+};
+
+use crate::stats::activity::Activity;
+
+/// Width of the column with the weekday labels
+const LABEL_WIDTH: usize = 4;
+
+/// Characters of the days by the number of solves relative to the maximum
+const LEVELS: [char; 5] = ['·', '░', '▒', '▓', '█'];
+
+/// Calendar heatmap of the number of solves on each day, each column is one
+/// week ending with the week of the current day
+#[derive(Debug)]
+pub struct Calendar {
+    activity: Activity,
+    today: NaiveDate,
+}
+
+impl Calendar {
+    /// Creates new [`Calendar`] of given activity ending with given day
+    pub fn new(activity: Activity, today: NaiveDate) -> Self {
+        Self { activity, today }
+    }
+}
+
+impl Widget for Calendar {
+    fn render(&self, buffer: &mut Buffer) {
+        if buffer.height() < 10 || buffer.width() <= LABEL_WIDTH {
+            return;
+        }
+
+        let weeks = buffer.width() - LABEL_WIDTH;
+        let monday = self.today
+            - Duration::days(
+                self.today.weekday().num_days_from_monday() as i64
+            );
+        let start = monday - Duration::weeks(weeks as i64 - 1);
+
+        let gray = Style::new().fg(Color::Gray);
+        for (row, name) in [(0, "Mon"), (2, "Wed"), (4, "Fri")] {
+            let pos = Vec2::new(buffer.x(), buffer.y() + 1 + row);
+            buffer.set_str_styled(name, &pos, gray);
+        }
+
+        let max = self.activity.max();
+        let mut label_end = 0;
+        for week in 0..weeks {
+            let first = start + Duration::weeks(week as i64);
+            let x = buffer.x() + LABEL_WIDTH + week;
+            let month = first.format("%b").to_string();
+            let fits = x + month.len() <= buffer.x() + buffer.width();
+            if (week == 0 || first.day() <= 7) && x >= label_end && fits {
+                let pos = Vec2::new(x, buffer.y());
+                buffer.set_str_styled(&month, &pos, gray);
+                label_end = x + month.len() + 1;
+            }
+
+            for row in 0..7 {
+                let day = first + Duration::days(row);
+                if day > self.today {
+                    break;
+                }
+
+                let cnt = self.activity.solves(day);
+                let level = match cnt {
+                    0 => 0,
+                    cnt => (cnt * 4).div_ceil(max).clamp(1, 4),
+                };
+                let style = match level {
+                    0 => gray,
+                    _ => Style::new().fg(Color::Green),
+                };
+                let pos = Vec2::new(x, buffer.y() + 1 + row as usize);
+                buffer.set_str_styled(LEVELS[level].to_string(), &pos, style);
+            }
+        }
+        self.render_summary(buffer);
+    }
+
+    fn height(&self, size: &Vec2) -> usize {
+        size.y
+    }
+
+    fn width(&self, _size: &Vec2) -> usize {
+        55
+    }
+}
+
+impl Calendar {
+    /// Renders the legend and the streaks
+    fn render_summary(&self, buffer: &mut Buffer) {
+        let mut pos = Vec2::new(buffer.x() + LABEL_WIDTH, buffer.y() + 9);
+        let mut text = |text: String, color: Color| {
+            buffer.set_str_styled(&text, &pos, Style::new().fg(color));
+            pos.x += text.chars().count() + 2;
+        };
+
+        text("Less".into(), Color::Gray);
+        text(LEVELS[1..].iter().collect(), Color::Green);
+        text("More".into(), Color::Gray);
+        text(
+            format!(
+                "streak {} / longest {}",
+                self.activity.current_streak(self.today),
+                self.activity.longest_streak()
+            ),
+            Color::White,
+        );
+        text(format!("{} days", self.activity.active_days()), Color::Gray);
+    }
+}
+
+impl From<Calendar> for Element {
+    fn from(value: Calendar) -> Self {
+        Element::new(value)
+    }
+}
+
+impl From<Calendar> for Box<dyn Widget> {
+    fn from(value: Calendar) -> Self {
+        Box::new(value)
+    }
+}
@@ -0,0 +1,271 @@
+use std::time::Duration;
+
+use termint::{
+    buffer::Buffer,
+    enums::Color,
+    geometry::Vec2,
+    style::Style,
+    widgets::{Element, Widget},
+};
+
+use crate::stats::stat::format_duration;
+
+/// Width of the column with the time labels
+const LABEL_WIDTH: usize = 10;
+
+/// Bits of the braille dots on given column and row of the character
+const DOTS: [[u32; 4]; 2] =
+    [[0x01, 0x02, 0x04, 0x40], [0x08, 0x10, 0x20, 0x80]];
+
+/// Axis the solves are plotted over
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphAxis {
+    /// Number of the solve
+    Index,
+    /// Date of the solve
+    Date,
+}
+
+/// Times plotted in the graph with the same color
+#[derive(Debug, Clone)]
+pub struct Series {
+    name: String,
+    color: Color,
+    points: Vec<(f64, Option<Duration>)>,
+    line: bool,
+}
+
+impl Series {
+    /// Creates new [`Series`] with given name and color from given points,
+    /// where [`None`] time isn't plotted
+    pub fn new<T: Into<String>>(
+        name: T,
+        color: Color,
+        points: Vec<(f64, Option<Duration>)>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            color,
+            points,
+            line: false,
+        }
+    }
+
+    /// Connects the consecutive points with a line
+    pub fn line(mut self) -> Self {
+        self.line = true;
+        self
+    }
+}
+
+/// Graph plotting the solve times using braille characters
+#[derive(Debug)]
+pub struct Graph {
+    series: Vec<Series>,
+    labels: (String, String),
+}
+
+impl Graph {
+    /// Creates new [`Graph`] with given series, later series are drawn over
+    /// the previous ones
+    pub fn new(series: Vec<Series>) -> Self {
+        Self {
+            series,
+            labels: Default::default(),
+        }
+    }
+
+    /// Sets the labels of the start and the end of the horizontal axis
+    pub fn labels<T, U>(mut self, start: T, end: U) -> Self
+    where
+        T: Into<String>,
+        U: Into<String>,
+    {
+        self.labels = (start.into(), end.into());
+        self
+    }
+}
+
+impl Widget for Graph {
+    fn render(&self, buffer: &mut Buffer) {
+        if buffer.height() < 3 || buffer.width() <= LABEL_WIDTH {
+            return;
+        }
+
+        self.render_legend(buffer);
+        let Some((min, max)) = self.range(|(_, t)| t.map(|t| t.as_secs_f64()))
+        else {
+            let pos = Vec2::new(buffer.x(), buffer.y() + 1);
+            let style = Style::new().fg(Color::Gray);
+            buffer.set_str_styled("Not enough solves...", &pos, style);
+            return;
+        };
+        let Some(xrange) = self.range(|(x, t)| t.map(|_| *x)) else {
+            return;
+        };
+
+        let width = buffer.width() - LABEL_WIDTH;
+        let height = buffer.height() - 2;
+        let mut cells = vec![(0, Color::Default); width * height];
+        for series in self.series.iter() {
+            let mut last = None;
+            for (x, time) in series.points.iter() {
+                let Some(time) = time else {
+                    last = None;
+                    continue;
+                };
+                let dot = (
+                    Self::scale(*x, xrange, width * 2),
+                    // Dots are counted from the top
+                    height * 4
+                        - 1
+                        - Self::scale(
+                            time.as_secs_f64(),
+                            (min, max),
+                            height * 4,
+                        ),
+                );
+                match last {
+                    Some(last) if series.line => {
+                        Self::line(&mut cells, width, last, dot, series.color)
+                    }
+                    _ => Self::dot(&mut cells, width, dot, series.color),
+                }
+                last = Some(dot);
+            }
+        }
+
+        for (id, (bits, color)) in cells.iter().enumerate() {
+            if *bits == 0 {
+                continue;
+            }
+            let pos = Vec2::new(
+                buffer.x() + LABEL_WIDTH + id % width,
+                buffer.y() + 1 + id / width,
+            );
+            buffer.set_val(char::from_u32(0x2800 + bits).unwrap_or(' '), &pos);
+            buffer.set_fg(*color, &pos);
+        }
+        self.render_axes(buffer, min, max);
+    }
+
+    fn height(&self, size: &Vec2) -> usize {
+        size.y
+    }
+
+    fn width(&self, _size: &Vec2) -> usize {
+        55
+    }
+}
+
+impl Graph {
+    /// Renders names of the series in their colors
+    fn render_legend(&self, buffer: &mut Buffer) {
+        let mut pos = Vec2::new(buffer.x() + LABEL_WIDTH, buffer.y());
+        for series in self.series.iter() {
+            buffer.set_str_styled("⣿", &pos, Style::new().fg(series.color));
+            pos.x += 2;
+            buffer.set_str_styled(
+                &series.name,
+                &pos,
+                Style::new().fg(Color::Gray),
+            );
+            pos.x += series.name.chars().count() + 2;
+        }
+    }
+
+    /// Renders labels of the time axis and the horizontal axis
+    fn render_axes(&self, buffer: &mut Buffer, min: f64, max: f64) {
+        let top = Vec2::new(buffer.x(), buffer.y() + 1);
+        let bottom = Vec2::new(buffer.x(), buffer.y() + buffer.height() - 2);
+        for (time, pos) in [(max, top), (min, bottom)] {
+            let label = format_duration(Duration::from_secs_f64(time.max(0.)));
+            let style = Style::new().fg(Color::Gray);
+            buffer.set_str_styled(format!("{label:>8} ┤"), &pos, style);
+        }
+
+        let mut pos = Vec2::new(
+            buffer.x() + LABEL_WIDTH,
+            buffer.y() + buffer.height() - 1,
+        );
+        let style = Style::new().fg(Color::Gray);
+        buffer.set_str_styled(&self.labels.0, &pos, style);
+        pos.x = (buffer.x() + buffer.width())
+            .saturating_sub(self.labels.1.chars().count());
+        buffer.set_str_styled(&self.labels.1, &pos, style);
+    }
+
+    /// Gets the minimum and the maximum of the values, [`None`] when there
+    /// are no values. Equal minimum and maximum are moved apart.
+    fn range<F>(&self, get: F) -> Option<(f64, f64)>
+    where
+        F: Fn(&(f64, Option<Duration>)) -> Option<f64>,
+    {
+        let values = self.series.iter().flat_map(|s| s.points.iter());
+        let (min, max) = values.filter_map(get).fold(
+            None,
+            |range: Option<(f64, f64)>, v| match range {
+                Some((min, max)) => Some((min.min(v), max.max(v))),
+                None => Some((v, v)),
+            },
+        )?;
+        match min == max {
+            true => Some((min - 1., max + 1.)),
+            false => Some((min, max)),
+        }
+    }
+
+    /// Scales the value from given range to the dot index
+    fn scale(val: f64, (min, max): (f64, f64), len: usize) -> usize {
+        let pos = (val - min) / (max - min) * (len - 1) as f64;
+        (pos.round() as usize).min(len - 1)
+    }
+
+    /// Draws line between given dots
+    fn line(
+        cells: &mut [(u32, Color)],
+        width: usize,
+        from: (usize, usize),
+        to: (usize, usize),
+        color: Color,
+    ) {
+        let steps = from.0.abs_diff(to.0).max(from.1.abs_diff(to.1)).max(1);
+        for i in 0..=steps {
+            let t = i as f64 / steps as f64;
+            let lerp = |a: usize, b: usize| {
+                (a as f64 + (b as f64 - a as f64) * t).round() as usize
+            };
+            Self::dot(
+                cells,
+                width,
+                (lerp(from.0, to.0), lerp(from.1, to.1)),
+                color,
+            );
+        }
+    }
+
+    /// Draws dot on given position
+    fn dot(
+        cells: &mut [(u32, Color)],
+        width: usize,
+        (x, y): (usize, usize),
+        color: Color,
+    ) {
+        if let Some(cell) = cells.get_mut(y / 4 * width + x / 2) {
+            cell.0 |= DOTS[x % 2][y % 4];
+            cell.1 = color;
+        }
+    }
+}
+
+impl From<Graph> for Element {
+    fn from(value: Graph) -> Self {
+        Element::new(value)
+    }
+}
+
+impl From<Graph> for Box<dyn Widget> {
+    fn from(value: Graph) -> Self {
+        Box::new(value)
+    }
+}
@@ -0,0 +1,236 @@
+use std::time::Duration;
+
+use termint::{
+    buffer::Buffer,
+    enums::Color,
+    geometry::Vec2,
+    style::Style,
+    widgets::{Element, Widget},
+};
+
+use crate::stats::{distribution::Distribution, stat::format_duration};
+
+/// Width of the column with the count labels
+const LABEL_WIDTH: usize = 5;
+
+/// Characters of the bars filled by eighths
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Histogram of the solve times with the percentile markers
+#[derive(Debug)]
+pub struct Histogram {
+    dist: Distribution,
+    bucket: Duration,
+    markers: Vec<(String, Duration, Color)>,
+}
+
+impl Histogram {
+    /// Creates new [`Histogram`] of given distribution with given bucket
+    /// width, the width is increased when the buckets don't fit
+    pub fn new(dist: Distribution, bucket: Duration) -> Self {
+        Self {
+            dist,
+            bucket,
+            markers: vec![],
+        }
+    }
+
+    /// Adds marker of the given time with given name
+    pub fn marker<T: Into<String>>(
+        mut self,
+        name: T,
+        time: Duration,
+        color: Color,
+    ) -> Self {
+        self.markers.push((name.into(), time, color));
+        self
+    }
+}
+
+impl Widget for Histogram {
+    fn render(&self, buffer: &mut Buffer) {
+        if buffer.height() < 6 || buffer.width() <= LABEL_WIDTH {
+            return;
+        }
+
+        let gray = Style::new().fg(Color::Gray);
+        if self.dist.is_empty() {
+            let pos = *buffer.pos();
+            buffer.set_str_styled("Not enough solves...", &pos, gray);
+            return;
+        }
+
+        let width = buffer.width() - LABEL_WIDTH;
+        let (bucket, start, counts) = self.buckets(width);
+        self.render_summary(buffer, bucket);
+
+        let col = (width / counts.len()).max(1);
+        let height = buffer.height() - 4;
+        let max = counts.iter().copied().max().unwrap_or(1).max(1);
+        for (id, count) in counts.iter().enumerate() {
+            let mut eighths = count * height * 8 / max;
+            if *count > 0 {
+                eighths = eighths.max(1);
+            }
+            let x = buffer.x() + LABEL_WIDTH + id * col;
+            for row in 0..height {
+                let fill = eighths.saturating_sub(row * 8).min(8);
+                if fill == 0 {
+                    break;
+                }
+                let bar = BARS[fill - 1].to_string();
+                let y = buffer.y() + height + 1 - row;
+                for dx in 0..col.saturating_sub(1).max(1) {
+                    buffer.set_str_styled(&bar, &Vec2::new(x + dx, y), gray);
+                }
+            }
+        }
+
+        let label = |y: usize, count: usize| {
+            let pos = Vec2::new(buffer.x(), buffer.y() + y);
+            (format!("{count:>3} ┤"), pos)
+        };
+        for (text, pos) in [label(2, max), label(height + 1, 0)] {
+            buffer.set_str_styled(text, &pos, gray);
+        }
+
+        let end = start + bucket * counts.len() as u32;
+        let y = buffer.y() + buffer.height() - 2;
+        for (_, time, color) in self.markers.iter() {
+            let pos = (time.saturating_sub(start)).as_secs_f64()
+                / (end - start).as_secs_f64()
+                * (col * counts.len()) as f64;
+            let x = (pos as usize).min((col * counts.len()).saturating_sub(1));
+            let pos = Vec2::new(buffer.x() + LABEL_WIDTH + x, y);
+            buffer.set_str_styled("▲", &pos, Style::new().fg(*color));
+        }
+
+        let mut pos = Vec2::new(buffer.x() + LABEL_WIDTH, y + 1);
+        buffer.set_str_styled(format_duration(start), &pos, gray);
+        let end = format_duration(end);
+        pos.x = (buffer.x() + LABEL_WIDTH + col * counts.len())
+            .saturating_sub(end.chars().count());
+        buffer.set_str_styled(end, &pos, gray);
+    }
+
+    fn height(&self, size: &Vec2) -> usize {
+        size.y
+    }
+
+    fn width(&self, _size: &Vec2) -> usize {
+        55
+    }
+}
+
+impl Histogram {
+    /// Renders the mean, the standard deviation, the bucket width and the
+    /// markers on the next line. Parts not fitting the width are skipped.
+    fn render_summary(&self, buffer: &mut Buffer, bucket: Duration) {
+        let mut pos = *buffer.pos();
+        let end = buffer.x() + buffer.width();
+        let mut text = |text: String, color: Color, newline: bool| {
+            if newline {
+                pos = Vec2::new(buffer.x(), pos.y + 1);
+            }
+            let len = text.chars().count();
+            if pos.x + len > end {
+                return;
+            }
+            buffer.set_str_styled(&text, &pos, Style::new().fg(color));
+            pos.x += len + 2;
+        };
+
+        if let Some(mean) = self.dist.mean() {
+            text(
+                format!("mean {}", format_duration(mean)),
+                Color::White,
+                false,
+            );
+        }
+        if let Some(dev) = self.dist.std_dev() {
+            text(format!("σ {}", format_duration(dev)), Color::White, false);
+        }
+        let bucket = format!("bucket {:.2}s", bucket.as_secs_f64());
+        text(bucket, Color::Gray, false);
+
+        for (id, (name, time, color)) in self.markers.iter().enumerate() {
+            let marker = format!("▲{name} {}", format_duration(*time));
+            text(marker, *color, id == 0);
+        }
+    }
+
+    /// Gets the bucket width, the start of the first bucket and the counts
+    /// of the buckets, so that the buckets fit into given width
+    fn buckets(&self, width: usize) -> (Duration, Duration, Vec<usize>) {
+        let mut bucket = self.bucket;
+        // Aligning the buckets can add one more, so it has to be repeated
+        loop {
+            let len = self.dist.bucket_count(bucket);
+            if len <= width.max(1) {
+                break;
+            }
+            let scale = len.div_ceil(width.max(1)) as f64;
+            bucket = Duration::try_from_secs_f64(bucket.as_secs_f64() * scale)
+                .unwrap_or(Duration::MAX);
+        }
+        let (start, counts) = self.dist.histogram(bucket);
+        (bucket, start, counts)
+    }
+}
+
+impl From<Histogram> for Element {
+    fn from(value: Histogram) -> Self {
+        Element::new(value)
+    }
+}
+
+impl From<Histogram> for Box<dyn Widget> {
+    fn from(value: Histogram) -> Self {
+        Box::new(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::solve;
+
+    use super::*;
+
+    #[test]
+    fn buckets_fit_width() {
+        let solves = [1.5, 100.5].map(|s| solve(Duration::from_secs_f64(s)));
+        let hist =
+            Histogram::new(Distribution::new(&solves), Duration::from_secs(1));
+        let (_, _, counts) = hist.buckets(50);
+        assert!(counts.len() <= 50);
+        assert_eq!(counts.iter().sum::<usize>(), 2);
+
+        let mut buffer = Buffer::empty((0, 0, 55, 12));
+        hist.render(&mut buffer);
+    }
+
+    #[test]
+    fn summary_is_clipped() {
+        let solves = [61., 3599.].map(|s| solve(Duration::from_secs_f64(s)));
+        let mut hist =
+            Histogram::new(Distribution::new(&solves), Duration::from_secs(1));
+        for name in ["p25", "median", "p75", "p90"] {
+            hist = hist.marker(name, Duration::from_secs(3599), Color::Cyan);
+        }
+
+        let mut buffer = Buffer::empty((0, 0, 55, 12));
+        hist.render_summary(&mut buffer, Duration::from_secs(60));
+        assert!((0..55).all(|x| buffer[Vec2::new(x, 2)].val == ' '));
+        assert_ne!(buffer[Vec2::new(0, 1)].val, ' ');
+    }
+
+    #[test]
+    fn tiny_bucket_is_widened() {
+        let solves = [1., 3600.].map(|s| solve(Duration::from_secs_f64(s)));
+        let bucket = Duration::from_nanos(1);
+        let hist = Histogram::new(Distribution::new(&solves), bucket);
+        let (bucket, _, counts) = hist.buckets(50);
+        assert!(counts.len() <= 50);
+        assert!(bucket >= Duration::from_secs(60));
+    }
+}
@@ -1 +1,4 @@
+pub mod calendar;
+pub mod graph;
+pub mod histogram;
 pub mod table;
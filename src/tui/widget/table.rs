@@ -8,21 +8,54 @@ use termint::{
     widgets::{Element, Widget},
 };
 
-use crate::{
-    stats::{stat::Stat, stats_struct::Stats},
-    tui::stats::StatsState,
-};
+use crate::{stats::sort::SortKey, tui::stats::StatsState};
+
+/// Relative widths of the table columns
+pub const COLUMNS: [usize; 9] = [11, 8, 6, 6, 8, 8, 8, 8, 8];
+
+/// Names of the table columns
+const HEADER: [&str; COLUMNS.len()] = [
+    "Date", "Time", "Moves", "mps", "mo3", "ao5", "ao12", "ao50", "ao100",
+];
+
+/// Indices of the mo3, ao50 and ao100 columns, which are shown only when
+/// the table is wide enough
+const WIDE_COLUMNS: [usize; 3] = [4, 7, 8];
+
+/// Width of the table without the wide columns
+const WIDTH: usize = 55;
 
+/// Width of the table with all the columns
+const WIDE_WIDTH: usize = 82;
+
+/// Values of the table row columns
+pub type Row = [String; COLUMNS.len()];
+
+/// Table of the solves. It contains only the visible rows starting at the
+/// state offset, so the solves don't have to be loaded all at once.
 #[derive(Debug)]
 pub struct Table {
-    stats: Stats,
+    rows: Vec<Row>,
+    len: usize,
+    selected: usize,
     state: Rc<RefCell<StatsState>>,
 }
 
 impl Table {
-    /// Creates new table widget
-    pub fn new(stats: Stats, state: Rc<RefCell<StatsState>>) -> Self {
-        Self { stats, state }
+    /// Creates new table widget with given visible rows, total number of
+    /// rows and position of the selected row
+    pub fn new(
+        rows: Vec<Row>,
+        len: usize,
+        selected: usize,
+        state: Rc<RefCell<StatsState>>,
+    ) -> Self {
+        Self {
+            rows,
+            len,
+            selected,
+            state,
+        }
     }
 }
 
@@ -32,23 +65,28 @@ impl Widget for Table {
             return;
         }
 
-        self.auto_scroll(buffer.height() - 1);
+        // Visible height is used for scrolling before the next render
+        self.state.borrow_mut().height = buffer.height() - 1;
         self.render_scrollbar(buffer);
         self.render_header(buffer);
         let mut pos = *buffer.pos();
         pos.y += 1;
 
-        if self.stats.solves().is_empty() {
+        if self.len == 0 {
             let style = Style::new().fg(Color::Gray);
             buffer.set_str_styled("Not stats yet...", &pos, style);
         }
 
-        let selected = self.state.borrow().selected;
-        for i in self.state.borrow().offset..self.stats.solves().len() {
+        let state = self.state.borrow();
+        for (i, row) in self.rows.iter().enumerate() {
             if buffer.y() + buffer.height() <= pos.y {
                 break;
             }
-            self.render_stat(buffer, &mut pos, &self.stats[i], i == selected);
+            let style = match state.offset + i == self.selected {
+                true => Style::new().fg(Color::Cyan),
+                false => Style::new().fg(Color::Gray),
+            };
+            Self::render_row(buffer, &mut pos, row, style);
             pos.y += 1;
         }
     }
@@ -57,15 +95,20 @@ impl Widget for Table {
         size.y
     }
 
-    fn width(&self, _size: &Vec2) -> usize {
-        35
+    /// The wide columns are shown when there is enough space left for the
+    /// solve details next to the table
+    fn width(&self, size: &Vec2) -> usize {
+        match size.x >= WIDE_WIDTH + WIDTH {
+            true => WIDE_WIDTH,
+            false => WIDTH,
+        }
     }
 }
 
 impl Table {
     /// Renders scrollbar
     fn render_scrollbar(&self, buffer: &mut Buffer) {
-        let rat = self.stats.solves().len() as f32 / buffer.height() as f32;
+        let rat = self.len as f32 / buffer.height() as f32;
         let thumb_size = min(
             (buffer.height() as f32 / rat).floor() as usize,
             buffer.height(),
@@ -90,87 +133,45 @@ impl Table {
     }
 
     fn render_header(&self, buffer: &mut Buffer) {
-        let (dwidth, twidth, mwidth) = Self::calc_widths(buffer.width());
-        let mut pos = *buffer.pos();
-
         let style = Style::new().fg(Color::White).modifier(Modifier::BOLD);
-        buffer.set_str_styled(
-            "Date".chars().take(dwidth).collect::<String>(),
-            &pos,
-            style,
-        );
-        pos.x += dwidth;
-        buffer.set_str_styled(
-            "Time".chars().take(twidth).collect::<String>(),
-            &pos,
-            style,
-        );
-        pos.x += twidth;
-        buffer.set_str_styled(
-            "Moves".chars().take(mwidth).collect::<String>(),
-            &pos,
-            style,
-        );
+        let sort = self.state.borrow().sort;
+        let mut header = HEADER.map(String::from);
+        header[column(sort)].push(match sort {
+            SortKey::Date | SortKey::Mps => '▼',
+            SortKey::Time | SortKey::Moves => '▲',
+        });
+        let mut pos = *buffer.pos();
+        Self::render_row(buffer, &mut pos, &header, style);
     }
 
-    /// Renders given stat into the table
-    fn render_stat(
-        &self,
+    /// Renders row with given values of the columns
+    fn render_row(
         buffer: &mut Buffer,
         pos: &mut Vec2,
-        stat: &Stat,
-        selected: bool,
+        row: &Row,
+        style: Style,
     ) {
-        let (dwidth, twidth, mwidth) = Self::calc_widths(buffer.width());
-
-        let date = stat.date().format("%d/%m/%Y").to_string();
-        let time = stat.format_time();
-        let move_cnt = stat.moves_cnt().to_string();
-
-        let style = match selected {
-            true => Style::new().fg(Color::Cyan),
-            false => Style::new().fg(Color::Gray),
-        };
-
-        buffer.set_str_styled(
-            date.chars().take(dwidth).collect::<String>(),
-            pos,
-            style,
-        );
-        pos.x += dwidth;
-        buffer.set_str_styled(
-            time.chars().take(twidth).collect::<String>(),
-            pos,
-            style,
-        );
-        pos.x += twidth;
-        buffer.set_str_styled(
-            move_cnt.chars().take(mwidth).collect::<String>(),
-            pos,
-            style,
-        );
-        pos.x = buffer.x();
-    }
-
-    fn auto_scroll(&self, height: usize) {
-        let mut state = self.state.borrow_mut();
-        if state.selected < state.offset + 3 {
-            state.offset = state.offset.saturating_sub(1);
-        } else if state.selected + 3 >= state.offset + height {
-            state.offset = std::cmp::min(
-                state.offset + 1,
-                self.stats.solves().len().saturating_sub(height),
+        let widths = Self::calc_widths(buffer.width());
+        for (val, width) in row.iter().zip(widths) {
+            buffer.set_str_styled(
+                val.chars().take(width).collect::<String>(),
+                pos,
+                style,
             );
+            pos.x += width;
         }
+        pos.x = buffer.x();
     }
 
-    fn calc_widths(width: usize) -> (usize, usize, usize) {
-        let part = width.saturating_sub(2) as f64 / 20.;
-        (
-            (part * 9.) as usize,
-            (part * 9.) as usize,
-            (part * 4.) as usize,
-        )
+    /// Calculates widths of the columns, hidden columns have zero width
+    fn calc_widths(width: usize) -> [usize; COLUMNS.len()] {
+        let mut columns = COLUMNS;
+        if width < WIDE_WIDTH {
+            WIDE_COLUMNS.iter().for_each(|id| columns[*id] = 0);
+        }
+        let part = width.saturating_sub(2) as f64
+            / columns.iter().sum::<usize>() as f64;
+        columns.map(|c| (part * c as f64) as usize)
     }
 }
 
@@ -185,3 +186,29 @@ impl From<Table> for Box<dyn Widget> {
         Box::new(value)
     }
 }
+
+/// Gets index of the table column the given key sorts by
+fn column(sort: SortKey) -> usize {
+    match sort {
+        SortKey::Date => 0,
+        SortKey::Time => 1,
+        SortKey::Moves => 2,
+        SortKey::Mps => 3,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wide_columns_are_hidden() {
+        let widths = Table::calc_widths(WIDTH);
+        assert!(WIDE_COLUMNS.iter().all(|id| widths[*id] == 0));
+        assert!(widths.iter().sum::<usize>() <= WIDTH - 2);
+
+        let widths = Table::calc_widths(WIDE_WIDTH);
+        assert!(widths.iter().zip(COLUMNS).all(|(w, c)| *w >= c));
+        assert!(widths.iter().sum::<usize>() <= WIDE_WIDTH - 2);
+    }
+}